@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+alter table guilds
+    drop column active,
+    drop column left_at;
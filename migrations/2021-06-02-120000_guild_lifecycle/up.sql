@@ -0,0 +1,4 @@
+-- Your SQL goes here
+alter table guilds
+    add column active boolean not null default true,
+    add column left_at timestamp;
@@ -0,0 +1,4 @@
+-- Your SQL goes here
+create table user_opt_outs (
+    id bigint primary key
+);
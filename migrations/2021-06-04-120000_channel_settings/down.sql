@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+drop table channel_settings;
+
+alter table guilds drop column match_mode;
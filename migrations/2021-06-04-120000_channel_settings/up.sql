@@ -0,0 +1,12 @@
+-- Your SQL goes here
+alter table guilds add column match_mode text not null default 'exact';
+
+create table channel_settings (
+    channel_id bigint primary key,
+    guild_id bigint not null,
+    chain_threshold smallint,
+    style text,
+    remove_messages boolean,
+    alternate_member boolean,
+    match_mode text
+);
@@ -0,0 +1,2 @@
+-- Your SQL goes here
+alter table guilds add column manager_roles bigint[] not null default '{}'::bigint[];
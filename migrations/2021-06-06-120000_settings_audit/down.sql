@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+drop table settings_audit;
+
+alter table guilds drop column log_channel;
@@ -0,0 +1,14 @@
+-- Your SQL goes here
+alter table guilds add column log_channel bigint;
+
+create table settings_audit (
+    id serial primary key,
+    guild_id bigint not null,
+    actor_id bigint not null,
+    field text not null,
+    old_value text not null,
+    new_value text not null,
+    changed_at timestamp not null default now()
+);
+
+create index settings_audit_guild on settings_audit (guild_id, changed_at);
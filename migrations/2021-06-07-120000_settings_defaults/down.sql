@@ -0,0 +1,11 @@
+-- This file should undo anything in `up.sql`
+alter table guilds
+    alter column prefixes set default '{"cb."}'::text[],
+    alter column channel_filters set default '{}'::bigint[],
+    alter column blacklist set default true,
+    alter column style set default 'embed',
+    alter column remove_messages set default true,
+    alter column chain_threshold set default 6,
+    alter column alternate_member set default true,
+    alter column match_mode set default 'exact',
+    alter column manager_roles set default '{}'::bigint[];
@@ -0,0 +1,13 @@
+-- Your SQL goes here
+-- New guilds are inserted with every setting from DEFAULT_SETTINGS,
+-- so the database no longer keeps its own copy of the defaults
+alter table guilds
+    alter column prefixes drop default,
+    alter column channel_filters drop default,
+    alter column blacklist drop default,
+    alter column style drop default,
+    alter column remove_messages drop default,
+    alter column chain_threshold drop default,
+    alter column alternate_member drop default,
+    alter column match_mode drop default,
+    alter column manager_roles drop default;
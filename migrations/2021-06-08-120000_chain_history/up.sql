@@ -0,0 +1,24 @@
+-- Your SQL goes here
+create table chain_history (
+    id serial primary key,
+    guild_id bigint not null,
+    channel_id bigint not null,
+    message text not null,
+    length int not null,
+    -- Nullable so the starter and breaker can be removed when a user deletes their data
+    starter_id bigint,
+    breaker_id bigint,
+    ended_at timestamp not null default now()
+);
+
+create index chain_history_guild on chain_history (guild_id);
+
+create table chain_participants (
+    chain_id int not null references chain_history (id) on delete cascade,
+    user_id bigint not null,
+    messages int not null,
+    points bigint not null,
+    primary key (chain_id, user_id)
+);
+
+create index chain_participants_user on chain_participants (user_id);
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+alter table guilds drop column react_emoji;
+alter table guilds drop column log_responses;
@@ -0,0 +1,4 @@
+-- Your SQL goes here
+alter table guilds add column react_emoji text;
+alter table guilds add column log_responses boolean not null default false;
+alter table guilds alter column log_responses drop default;
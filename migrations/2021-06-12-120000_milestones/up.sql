@@ -0,0 +1,4 @@
+-- Your SQL goes here
+-- Existing guilds keep the announcements they had, new guilds get the defaults from the bot
+alter table guilds add column milestones smallint[] not null default '{}';
+alter table guilds alter column milestones drop default;
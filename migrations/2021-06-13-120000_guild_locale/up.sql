@@ -0,0 +1,2 @@
+-- Your SQL goes here
+alter table guilds add column locale text;
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+alter table guilds add column ping_participants boolean not null default false;
+alter table guilds alter column ping_participants drop default;
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+alter table guilds add column replay_chains boolean not null default false;
+alter table guilds alter column replay_chains drop default;
@@ -0,0 +1,155 @@
+use std::fmt::Display;
+
+use serenity::{
+    client::Context,
+    model::id::{ChannelId, GuildId, RoleId, UserId},
+    prelude::TypeMap,
+};
+
+use crate::{
+    bot::{guild_settings::GuildSettingsCache, locale::guild_locale},
+    database::tables::audit::record_setting_change,
+    t,
+    DatabaseConn,
+};
+
+/// A change made to one of a guild's settings
+pub struct SettingChange {
+    pub field: String,
+    pub old_value: String,
+    pub new_value: String,
+}
+
+impl SettingChange {
+    pub fn new(field: impl Display, old_value: impl Display, new_value: impl Display) -> Self {
+        SettingChange {
+            field: field.to_string(),
+            old_value: or_none(old_value.to_string()),
+            new_value: or_none(new_value.to_string()),
+        }
+    }
+
+    /// Whether the setting ended up the same as it was, like removing a prefix that wasn't set
+    pub fn is_unchanged(&self) -> bool {
+        self.old_value == self.new_value
+    }
+}
+
+/// A change that has been written to the audit log but not posted to the log channel yet
+///
+/// Posting waits on Discord, so `post` it after the data and settings locks are dropped
+#[must_use]
+pub struct AuditEntry {
+    guild_id: GuildId,
+    actor: UserId,
+    change: SettingChange,
+    channels: Vec<ChannelId>,
+    /// The guild's locale setting, the entry is posted in the guild's language
+    locale: Option<String>,
+}
+
+/// Records a change to the audit log, returning the entry to post in the guild's log channel
+///
+/// Takes the already locked data and settings, the settings commands hold both while changing them
+pub async fn record_change(
+    data: &TypeMap,
+    settings: &GuildSettingsCache,
+    guild_id: GuildId,
+    actor: UserId,
+    change: SettingChange,
+) -> AuditEntry {
+    // Changes that didn't change anything aren't worth a line in the history
+    if !change.is_unchanged() {
+        let database = data.get::<DatabaseConn>().unwrap().lock().await;
+        record_setting_change(
+            &database,
+            guild_id,
+            actor,
+            &change.field,
+            &change.old_value,
+            &change.new_value,
+        );
+    }
+
+    AuditEntry {
+        guild_id,
+        actor,
+        change,
+        channels: settings.log_channel(guild_id).into_iter().collect(),
+        locale: settings.locale(guild_id),
+    }
+}
+
+impl AuditEntry {
+    /// Posts the entry in another channel as well, like the log channel before it was changed
+    pub fn also_post_in(mut self, channel: Option<ChannelId>) -> Self {
+        if let Some(c) = channel {
+            if !self.channels.contains(&c) {
+                self.channels.push(c);
+            }
+        }
+
+        self
+    }
+
+    /// Posts the entry in the log channels, unless nothing changed
+    pub async fn post(self, ctx: &Context) {
+        if self.channels.is_empty() || self.change.is_unchanged() {
+            return;
+        }
+
+        let locale = guild_locale(ctx, self.guild_id, self.locale.as_deref()).await;
+
+        for channel in &self.channels {
+            let result = channel
+                .send_message(ctx, |m| {
+                    m.embed(|e| {
+                        e.title(t!(locale, "audit.title"));
+                        e.description(t!(
+                            locale,
+                            "audit.description",
+                            user = format!("<@{}>", self.actor.0),
+                            setting = self.change.field
+                        ));
+                        e.field(t!(locale, "audit.old"), &self.change.old_value, true);
+                        e.field(t!(locale, "audit.new"), &self.change.new_value, true);
+                        e
+                    });
+                    m.allowed_mentions(|a| a.empty_parse())
+                })
+                .await;
+
+            // Not being able to post in the log channel shouldn't stop the change
+            if let Err(e) = result {
+                println!("Error posting to log channel in {}: {:?}", self.guild_id, e);
+            }
+        }
+    }
+}
+
+/// Formats a list of channels as mentions
+pub fn channel_list(channels: &[ChannelId]) -> String {
+    channels
+        .iter()
+        .map(|c| format!("<#{}>", c.0))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Formats a list of roles as mentions
+pub fn role_list(roles: &[RoleId]) -> String {
+    roles
+        .iter()
+        .map(|r| format!("<@&{}>", r.0))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Embed fields can't be empty
+fn or_none(value: String) -> String {
+    if value.is_empty() {
+        "none".to_owned()
+    } else {
+        value
+    }
+}
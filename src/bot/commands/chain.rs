@@ -0,0 +1,173 @@
+use serenity::{builder::CreateEmbed, model::id::UserId};
+use slashy::{command, commands::CommandResult, framework::CommandContext, subcommand};
+
+use crate::{
+    bot::locale::command_locale,
+    chain::{points::points_per_user, Chain, ChainCounter},
+    t,
+};
+
+/// Embed field values can be at most 1024 characters
+const MAX_FIELD_LENGTH: usize = 1024;
+
+command! {
+    chain,
+    "see the chains running in this channel or the whole server",
+    chain_status,
+    [
+        optional Boolean all | "whether to show every chain in the server"
+    ]
+}
+
+#[subcommand]
+async fn chain_status(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let all = *ctx.get_bool_arg("all").unwrap_or(&false);
+    let guild_id = match ctx.guild_id() {
+        Some(g) => g,
+        None => {
+            ctx.send_str(&t!(locale, "chain.servers_only")).await?;
+            return Ok(());
+        }
+    };
+    let channel_id = ctx.channel().await?.id();
+    let caller = ctx.author().unwrap().id;
+
+    // Copied out so the chain handler isn't kept waiting on the lock while we talk to Discord
+    let data = ctx.ctx.data.read().await;
+    let chains = data.get::<ChainCounter>().unwrap();
+    let mut guild_chains = chains
+        .iter()
+        .filter(|(_, c)| c.starter.guild_id == guild_id)
+        .filter(|(c, _)| all || **c == channel_id)
+        .map(|(id, c)| (*id, c.clone()))
+        .collect::<Vec<_>>();
+    drop(data);
+
+    if all {
+        guild_chains.sort_by_key(|(_, c)| std::cmp::Reverse(c.length));
+
+        if guild_chains.is_empty() {
+            ctx.send_str(&t!(locale, "chain.none_in_server")).await?;
+            return Ok(());
+        }
+
+        // Embeds can have at most 25 fields
+        guild_chains.truncate(25);
+
+        let mut names = Vec::new();
+        for (channel, _) in &guild_chains {
+            names.push(channel.name(&ctx.ctx.cache).await.unwrap_or_default());
+        }
+
+        ctx.send_embed(|e: &mut CreateEmbed| {
+            e.title(t!(locale, "chain.running_title"));
+            for ((_, chain), name) in guild_chains.iter().zip(&names) {
+                e.field(
+                    format!("#{}", name),
+                    t!(
+                        locale,
+                        "chain.running_entry",
+                        message = chain_text(chain, 100, locale),
+                        length = chain.length,
+                        members = chain.chainers.len()
+                    ),
+                    false,
+                );
+            }
+            e
+        })
+        .await?;
+    } else {
+        let chain = match guild_chains.first() {
+            Some((_, c)) => c,
+            None => {
+                ctx.send_str(&t!(locale, "chain.none_in_channel")).await?;
+                return Ok(());
+            }
+        };
+
+        ctx.send_embed(|e: &mut CreateEmbed| {
+            e.title(t!(locale, "chain.current_title"));
+            e.field(
+                t!(locale, "chain.message"),
+                chain_text(chain, MAX_FIELD_LENGTH - 2, locale),
+                false,
+            );
+            e.field(t!(locale, "chain.length"), chain.length, true);
+            e.field(
+                t!(locale, "chain.started_by"),
+                format!("<@{}>", chain.starter.user.id.0),
+                true,
+            );
+            e.field(t!(locale, "chain.participants"), participants(chain), false);
+            e.field(
+                t!(locale, "chain.projected_points"),
+                projected_points(chain, caller, locale),
+                false,
+            );
+            e
+        })
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Lists the members in a chain with how many messages they sent
+fn participants(chain: &Chain) -> String {
+    let list = chain
+        .chainers
+        .iter()
+        .map(|u| format!("<@{}>: {}", u.0, chain.num_messages.get(u).unwrap_or(&0)))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    truncate(&list, MAX_FIELD_LENGTH)
+}
+
+/// The points everyone would get if `breaker` broke the chain
+///
+/// The total is random within a range, so this is only an estimate
+fn projected_points(chain: &Chain, breaker: UserId, locale: &str) -> String {
+    let mut points = points_per_user(chain, breaker).into_iter().collect::<Vec<_>>();
+    points.sort_by_key(|(_, p)| std::cmp::Reverse(*p));
+
+    if points.is_empty() {
+        return t!(locale, "chain.no_points");
+    }
+
+    let list = points
+        .iter()
+        .map(|(u, p)| {
+            t!(
+                locale,
+                "chain.points_entry",
+                user = format!("<@{}>", u.0),
+                points = p
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    truncate(&list, MAX_FIELD_LENGTH)
+}
+
+/// The chain's message, chains of attachments have no text and embed fields can't be empty
+fn chain_text(chain: &Chain, max: usize, locale: &str) -> String {
+    if chain.message.trim().is_empty() {
+        t!(locale, "chain.attachments_only")
+    } else {
+        truncate(&chain.message, max)
+    }
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        text.to_owned()
+    } else {
+        let mut text = text.chars().take(max - 1).collect::<String>();
+        text.push('…');
+        text
+    }
+}
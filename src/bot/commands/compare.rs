@@ -0,0 +1,113 @@
+use serenity::{builder::CreateEmbed, utils::Colour};
+use slashy::{command, commands::CommandResult, framework::CommandContext, subcommand};
+
+use crate::{
+    bot::{locale::command_locale, members::MemberNames},
+    database::{
+        get_or_create_user,
+        tables::{
+            history::{get_chain_stats, get_shared_chains, get_times_broken},
+            leaderboards::{get_global_rank, get_or_create_server_user, get_server_rank},
+        },
+    },
+    t,
+    DatabaseConn,
+};
+
+command! {
+    compare,
+    "compare the stats of two users",
+    compare,
+    [
+        required User user_a | "the first user to compare",
+        required User user_b | "the second user to compare"
+    ]
+}
+
+#[subcommand]
+async fn compare(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let user_a = *ctx.get_user_arg("user_a").unwrap();
+    let user_b = *ctx.get_user_arg("user_b").unwrap();
+
+    let guild = match ctx.guild_id() {
+        Some(g) => g,
+        None => {
+            ctx.send_str(&t!(locale, "compare.servers_only")).await?;
+            return Ok(());
+        }
+    };
+
+    if user_a == user_b {
+        ctx.send_str(&t!(locale, "compare.same_user")).await?;
+        return Ok(());
+    }
+
+    let data = ctx.ctx.data.read().await;
+    let database = data.get::<DatabaseConn>().unwrap().lock().await;
+
+    let summaries = [user_a, user_b]
+        .iter()
+        .map(|u| {
+            let user = get_or_create_user(&database, *u);
+            let server_user = get_or_create_server_user(&database, guild, *u);
+            let chain_stats = get_chain_stats(&database, guild, *u);
+
+            t!(
+                locale,
+                "compare.summary",
+                server_points = server_user.points,
+                server_rank = get_server_rank(&database, guild, server_user.points),
+                server_chains = chain_list(&server_user.longest_chains),
+                global_points = user.points,
+                global_rank = get_global_rank(&database, user.points),
+                global_chains = chain_list(&user.longest_chains),
+                joined = chain_stats.joined,
+                started = chain_stats.started,
+                broken = chain_stats.broken
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let shared = get_shared_chains(&database, guild, user_a, user_b);
+    let a_broke_b = get_times_broken(&database, guild, user_a, user_b);
+    let b_broke_a = get_times_broken(&database, guild, user_b, user_a);
+
+    drop(database);
+
+    let mut names = MemberNames::new(&ctx.ctx, Some(guild), locale);
+    names.resolve(vec![user_a, user_b]).await;
+    let name_a = names.name(user_a);
+    let name_b = names.name(user_b);
+
+    ctx.send_embed(|e: &mut CreateEmbed| {
+        e.title(t!(locale, "compare.title", a = name_a, b = name_b))
+            .field(&name_a, &summaries[0], true)
+            .field(&name_b, &summaries[1], true)
+            .field(
+                t!(locale, "compare.head_to_head"),
+                t!(
+                    locale,
+                    "compare.head_to_head_value",
+                    shared = shared,
+                    a = name_a,
+                    b = name_b,
+                    a_broke_b = a_broke_b,
+                    b_broke_a = b_broke_a
+                ),
+                false,
+            )
+            .color(Colour::MAGENTA)
+    })
+    .await?;
+
+    Ok(())
+}
+
+fn chain_list(chains: &[i32]) -> String {
+    chains
+        .iter()
+        .map(|c| c.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
@@ -1,8 +1,20 @@
 mod stats;
 pub use stats::STATS_COMMAND;
 
+mod compare;
+pub use compare::COMPARE_COMMAND;
+
 mod top;
 pub use top::TOP_COMMAND;
 
 mod settings;
-pub use settings::SETTINGS_COMMAND;
+pub use settings::SettingsCommand;
+
+mod panel;
+pub use panel::settings_component;
+
+mod chain;
+pub use chain::CHAIN_COMMAND;
+
+mod privacy;
+pub use privacy::{privacy_component, OptOutStore, PRIVACY_COMMAND};
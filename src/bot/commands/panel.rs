@@ -0,0 +1,318 @@
+use serenity::{
+    builder::{CreateComponents, CreateEmbed},
+    client::Context,
+    model::{
+        channel::{ChannelType, GuildChannel},
+        id::{ChannelId, GuildId},
+        interactions::{
+            message_component::{ButtonStyle, MessageComponentInteraction},
+            InteractionResponseType,
+        },
+    },
+};
+use slashy::{commands::CommandResult, framework::CommandContext, subcommand};
+
+use super::settings::reset_component;
+
+use crate::{
+    bot::{
+        audit::{channel_list, record_change, SettingChange},
+        components::respond_ephemeral,
+        guild_settings::{GuildSettings, GuildSettingsStore},
+        locale::{command_locale, interaction_locale},
+        permissions::{is_settings_manager, SETTINGS_MANAGER},
+    },
+    chain::styles::STYLES,
+    t,
+};
+
+/// Select menus can have at most 25 options
+const MAX_MENU_OPTIONS: usize = 25;
+
+#[subcommand(SETTINGS_MANAGER)]
+pub async fn settings_panel(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let guild_id = ctx.guild_id().unwrap();
+    let channels = panel_channels(&ctx.ctx, guild_id).await?;
+
+    let data = ctx.ctx.data.read().await;
+    let settings = data
+        .get::<GuildSettingsStore>()
+        .unwrap()
+        .read()
+        .await
+        .get_or_default(guild_id);
+
+    ctx.send_message(|m| {
+        m.set_embed(panel_embed(&settings, locale));
+        m.set_components(panel_components(&settings, &channels, locale));
+        m.allowed_mentions(|a| a.empty_parse());
+        m
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Handles the buttons and select menus on the settings panel
+///
+/// Custom ids are `settings:<setting>`, select menus send their choices as the values
+pub async fn settings_component(
+    ctx: &Context,
+    interaction: &MessageComponentInteraction,
+    args: &[&str],
+) -> CommandResult {
+    let (guild_id, member) = match (interaction.guild_id, &interaction.member) {
+        (Some(g), Some(m)) => (g, m),
+        _ => return Ok(()),
+    };
+
+    let data = ctx.data.read().await;
+    let locale_setting = data
+        .get::<GuildSettingsStore>()
+        .unwrap()
+        .read()
+        .await
+        .locale(guild_id);
+    let locale = interaction_locale(locale_setting.as_deref(), &interaction.locale);
+    drop(data);
+
+    if !is_settings_manager(ctx, member).await? {
+        return respond_ephemeral(ctx, interaction, &t!(locale, "panel.not_manager")).await;
+    }
+
+    // The confirmation buttons from `/settings reset` share the settings custom ids
+    if args.first() == Some(&"reset") {
+        return reset_component(ctx, interaction, guild_id, &args[1..]).await;
+    }
+
+    let channels = panel_channels(ctx, guild_id).await?;
+
+    let data = ctx.data.write().await;
+    let mut settings = data.get::<GuildSettingsStore>().unwrap().write().await;
+
+    let change = match args.first().copied() {
+        Some("blacklist") => {
+            *settings.blacklist_mut(guild_id) ^= true;
+            let new = settings.blacklist(guild_id);
+            SettingChange::new("blacklist", !new, new)
+        }
+        Some("remove_messages") => {
+            *settings.remove_messages_mut(guild_id) ^= true;
+            let new = settings.remove_messages(guild_id);
+            SettingChange::new("remove_messages", !new, new)
+        }
+        Some("alternate_member") => {
+            *settings.alternate_member_mut(guild_id) ^= true;
+            let new = settings.alternate_member(guild_id);
+            SettingChange::new("alternate_member", !new, new)
+        }
+        Some("style") => {
+            let style = match interaction.data.values.first().map(|v| v.parse()) {
+                Some(Ok(s)) => s,
+                _ => return Ok(()),
+            };
+
+            let old = settings.style(guild_id);
+            *settings.style_mut(guild_id) = style;
+            SettingChange::new("style", old, style)
+        }
+        Some("filters") => {
+            let old = settings.channel_filters(guild_id);
+            let selected = interaction
+                .data
+                .values
+                .iter()
+                .filter_map(|v| v.parse().ok())
+                .map(ChannelId);
+
+            // Filters on channels that didn't fit in the menu can't be deselected, so keep them
+            let filters = settings.channel_filters_mut(guild_id);
+            filters.retain(|f| !channels.iter().any(|c| c.id == *f));
+            filters.extend(selected);
+
+            SettingChange::new(
+                "channel_filters",
+                channel_list(&old),
+                channel_list(&settings.channel_filters(guild_id)),
+            )
+        }
+        _ => return Ok(()),
+    };
+
+    let entry = record_change(&data, &settings, guild_id, interaction.user.id, change).await;
+
+    let current = settings.get_or_default(guild_id);
+    drop(settings);
+    drop(data);
+
+    interaction
+        .create_interaction_response(ctx, |r| {
+            r.kind(InteractionResponseType::UpdateMessage);
+            r.interaction_response_data(|d| {
+                d.add_embed(panel_embed(&current, locale));
+                d.set_components(panel_components(&current, &channels, locale));
+                d.allowed_mentions(|a| a.empty_parse());
+                d
+            });
+            r
+        })
+        .await?;
+
+    entry.post(ctx).await;
+
+    Ok(())
+}
+
+/// The text channels that can be picked in the filter menu, in the order they show in the server
+async fn panel_channels(ctx: &Context, guild_id: GuildId) -> CommandResult<Vec<GuildChannel>> {
+    let mut channels = guild_id
+        .channels(ctx)
+        .await?
+        .into_iter()
+        .map(|(_, c)| c)
+        .filter(|c| c.kind == ChannelType::Text)
+        .collect::<Vec<_>>();
+
+    channels.sort_by_key(|c| c.position);
+    channels.truncate(MAX_MENU_OPTIONS);
+
+    Ok(channels)
+}
+
+fn panel_embed(settings: &GuildSettings, locale: &str) -> CreateEmbed {
+    let mut e = CreateEmbed::default();
+
+    let channels = if settings.channel_filters.is_empty() {
+        t!(locale, "panel.no_channels")
+    } else {
+        channel_list(&settings.channel_filters)
+    };
+    let filters = if settings.blacklist {
+        t!(locale, "settings.filters_blacklisted", channels = channels)
+    } else {
+        t!(locale, "settings.filters_whitelisted", channels = channels)
+    };
+
+    e.title(t!(locale, "panel.title"));
+    e.field(
+        t!(locale, "settings.field.prefixes"),
+        format!("{:?}", settings.prefixes),
+        false,
+    );
+    e.field(t!(locale, "settings.field.channel_filters"), filters, false);
+    e.field(
+        t!(locale, "settings.field.style"),
+        settings.style.to_string(),
+        true,
+    );
+    e.field(
+        t!(locale, "settings.field.chain_threshold"),
+        settings.chain_threshold.to_string(),
+        true,
+    );
+    e.field(
+        t!(locale, "settings.field.match_mode"),
+        settings.match_mode.to_string(),
+        true,
+    );
+    e.field(
+        t!(locale, "settings.field.remove_messages"),
+        settings.remove_messages.to_string(),
+        true,
+    );
+    e.field(
+        t!(locale, "settings.field.alternate_member"),
+        settings.alternate_member.to_string(),
+        true,
+    );
+
+    e
+}
+
+fn panel_components(
+    settings: &GuildSettings,
+    channels: &[GuildChannel],
+    locale: &str,
+) -> CreateComponents {
+    let mut c = CreateComponents::default();
+
+    c.create_action_row(|r| {
+        for (setting, label, on) in [
+            ("blacklist", "panel.button.blacklist", settings.blacklist),
+            (
+                "remove_messages",
+                "panel.button.remove_messages",
+                settings.remove_messages,
+            ),
+            (
+                "alternate_member",
+                "panel.button.alternate_member",
+                settings.alternate_member,
+            ),
+        ] {
+            let state = if on {
+                t!(locale, "panel.on")
+            } else {
+                t!(locale, "panel.off")
+            };
+
+            r.create_button(|b| {
+                b.style(if on {
+                    ButtonStyle::Success
+                } else {
+                    ButtonStyle::Secondary
+                })
+                .label(t!(
+                    locale,
+                    "panel.button",
+                    label = t!(locale, label),
+                    state = state
+                ))
+                .custom_id(format!("settings:{}", setting))
+            });
+        }
+        r
+    });
+
+    c.create_action_row(|r| {
+        r.create_select_menu(|m| {
+            m.custom_id("settings:style");
+            m.placeholder(t!(locale, "panel.style_placeholder"));
+            m.options(|o| {
+                for style in STYLES.names() {
+                    o.create_option(|opt| {
+                        opt.label(style)
+                            .value(style)
+                            .default_selection(style == settings.style.name())
+                    });
+                }
+                o
+            })
+        })
+    });
+
+    // A select menu needs at least one option
+    if !channels.is_empty() {
+        c.create_action_row(|r| {
+            r.create_select_menu(|m| {
+                m.custom_id("settings:filters");
+                m.placeholder(t!(locale, "panel.filters_placeholder"));
+                m.min_values(0);
+                m.max_values(channels.len() as u64);
+                m.options(|o| {
+                    for channel in channels {
+                        o.create_option(|opt| {
+                            opt.label(format!("#{}", channel.name))
+                                .value(channel.id.0)
+                                .default_selection(settings.channel_filters.contains(&channel.id))
+                        });
+                    }
+                    o
+                })
+            })
+        });
+    }
+
+    c
+}
@@ -0,0 +1,229 @@
+use std::{borrow::Cow, collections::HashSet, time::UNIX_EPOCH};
+
+use serde_json::json;
+use serenity::{
+    client::Context,
+    http::AttachmentType,
+    model::{
+        id::UserId,
+        interactions::{
+            message_component::{ButtonStyle, MessageComponentInteraction},
+            InteractionResponseType,
+        },
+    },
+    prelude::TypeMapKey,
+};
+use slashy::{command, commands::CommandResult, framework::CommandContext, subcommand};
+
+use crate::{
+    bot::{
+        components::respond_ephemeral,
+        guild_settings::GuildSettingsStore,
+        locale::{command_locale, interaction_locale},
+    },
+    database::{
+        get_user,
+        tables::{
+            audit::get_user_setting_changes,
+            history::get_user_participations,
+            leaderboards::get_server_users,
+            privacy::{delete_user_data, set_opted_out},
+        },
+    },
+    t,
+    DatabaseConn,
+};
+
+/// The users who have asked for their messages to be ignored
+pub struct OptOutStore;
+
+impl TypeMapKey for OptOutStore {
+    type Value = HashSet<UserId>;
+}
+
+command! {
+    privacy,
+    "see or delete the data stored about you",
+    [
+        optional SubCommand export = export_data | "DM you a copy of all the data stored about you",
+        optional SubCommand delete = delete_data | "Delete all the data stored about you",
+        optional SubCommand opt_out = opt_out | "Flip whether your messages are ignored in chains"
+    ]
+}
+
+#[subcommand]
+async fn export_data(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let author = ctx.author().unwrap();
+    let data = ctx.ctx.data.read().await;
+    let database = data.get::<DatabaseConn>().unwrap().lock().await;
+
+    let user = get_user(&database, author.id);
+    let server_users = get_server_users(&database, author.id);
+    let participations = get_user_participations(&database, author.id);
+    let setting_changes = get_user_setting_changes(&database, author.id);
+
+    drop(database);
+
+    let export = json!({
+        "user": user.map(|u| json!({
+            "id": u.id.0.to_string(),
+            "points": u.points,
+            "longest_chains": u.longest_chains,
+        })),
+        "servers": server_users
+            .iter()
+            .map(|u| json!({
+                "server_id": u.server_id.0.to_string(),
+                "points": u.points,
+                "longest_chains": u.longest_chains,
+            }))
+            .collect::<Vec<_>>(),
+        "chains": participations
+            .iter()
+            .map(|p| json!({
+                "chain_id": p.chain_id,
+                "server_id": p.guild_id.0.to_string(),
+                "channel_id": p.channel_id.0.to_string(),
+                "length": p.length,
+                "messages": p.messages,
+                "points": p.points,
+                "ended_at": p.ended_at.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+            }))
+            .collect::<Vec<_>>(),
+        "setting_changes": setting_changes
+            .iter()
+            .map(|c| json!({
+                "server_id": c.guild_id.0.to_string(),
+                "setting": c.field,
+                "old_value": c.old_value,
+                "new_value": c.new_value,
+                "changed_at": c.changed_at.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+            }))
+            .collect::<Vec<_>>(),
+        "opted_out": data.get::<OptOutStore>().unwrap().contains(&author.id),
+    });
+    let export = serde_json::to_vec_pretty(&export)?;
+
+    author
+        .direct_message(&ctx.ctx, |m| {
+            m.content(t!(locale, "privacy.export_dm"));
+            m.add_file(AttachmentType::Bytes {
+                data: Cow::from(export),
+                filename: "chain_bot_data.json".to_owned(),
+            });
+            m
+        })
+        .await?;
+
+    ctx.send_str(&t!(locale, "privacy.export_sent")).await?;
+
+    Ok(())
+}
+
+#[subcommand]
+async fn delete_data(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let author_id = ctx.author().unwrap().id;
+
+    ctx.send_message(|m| {
+        m.content(t!(locale, "privacy.delete_confirm"));
+        m.components(|c| {
+            c.create_action_row(|r| {
+                r.create_button(|b| {
+                    b.style(ButtonStyle::Danger)
+                        .label(t!(locale, "privacy.delete_button"))
+                        .custom_id(format!("privacy:delete:{}", author_id))
+                });
+                r.create_button(|b| {
+                    b.style(ButtonStyle::Secondary)
+                        .label(t!(locale, "privacy.cancel_button"))
+                        .custom_id(format!("privacy:cancel:{}", author_id))
+                })
+            })
+        });
+        m.allowed_mentions(|a| a.empty_parse());
+        m
+    })
+    .await?;
+
+    Ok(())
+}
+
+#[subcommand]
+async fn opt_out(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let author_id = ctx.author().unwrap().id;
+    let mut data = ctx.ctx.data.write().await;
+
+    let opt_outs = data.get_mut::<OptOutStore>().unwrap();
+    let opted_out = !opt_outs.remove(&author_id);
+    if opted_out {
+        opt_outs.insert(author_id);
+    }
+
+    let database = data.get::<DatabaseConn>().unwrap().lock().await;
+    set_opted_out(&database, author_id, opted_out);
+    drop(database);
+
+    ctx.send_str(&if opted_out {
+        t!(locale, "privacy.opted_out")
+    } else {
+        t!(locale, "privacy.opted_in")
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Handles the confirmation buttons sent by `privacy delete`
+///
+/// Arguments: action, user id
+pub async fn privacy_component(
+    ctx: &Context,
+    interaction: &MessageComponentInteraction,
+    args: &[&str],
+) -> CommandResult {
+    let data = ctx.data.read().await;
+    let locale_setting = match interaction.guild_id {
+        Some(g) => data
+            .get::<GuildSettingsStore>()
+            .unwrap()
+            .read()
+            .await
+            .locale(g),
+        None => None,
+    };
+    let locale = interaction_locale(locale_setting.as_deref(), &interaction.locale);
+    drop(data);
+
+    // Only the user who asked can confirm deleting their data
+    if args.get(1) != Some(&interaction.user.id.to_string().as_str()) {
+        return respond_ephemeral(ctx, interaction, &t!(locale, "privacy.not_yours")).await;
+    }
+
+    let content = match args[0] {
+        "delete" => {
+            let data = ctx.data.read().await;
+            let database = data.get::<DatabaseConn>().unwrap().lock().await;
+            delete_user_data(&database, interaction.user.id);
+            t!(locale, "privacy.deleted")
+        }
+        _ => t!(locale, "privacy.cancelled"),
+    };
+
+    interaction
+        .create_interaction_response(ctx, |r| {
+            r.kind(InteractionResponseType::UpdateMessage);
+            r.interaction_response_data(|d| {
+                d.content(content);
+                d.components(|c| c);
+                d.allowed_mentions(|a| a.empty_parse());
+                d
+            });
+            r
+        })
+        .await?;
+
+    Ok(())
+}
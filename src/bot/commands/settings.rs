@@ -1,6 +1,19 @@
+use std::{borrow::Cow, time::UNIX_EPOCH};
+
 use serenity::{
+    client::Context,
     futures::future::join_all,
-    model::{channel::Channel, Permissions},
+    http::AttachmentType,
+    model::{
+        channel::Channel,
+        id::{ChannelId, GuildId},
+        interactions::{
+            message_component::{ButtonStyle, MessageComponentInteraction},
+            InteractionResponseType,
+        },
+        Permissions,
+    },
+    prelude::TypeMap,
 };
 use slashy::{
     command,
@@ -10,7 +23,39 @@ use slashy::{
     subcommand,
 };
 
-use crate::bot::guild_settings::{GuildSettingsStore, DM_SETTINGS};
+use crate::{
+    bot::{
+        audit::{channel_list, record_change, role_list, AuditEntry, SettingChange},
+        components::respond_ephemeral,
+        guild_settings::{
+            parse_milestone,
+            parse_threshold,
+            validate_locale,
+            validate_milestones,
+            validate_prefixes,
+            validate_react_emoji,
+            GuildSettings,
+            GuildSettingsCache,
+            GuildSettingsStore,
+            MatchMode,
+            SettingsError,
+            StyleName,
+            DEFAULT_SETTINGS,
+        },
+        locale::{command_locale, interaction_locale, LOCALES},
+        mentions::send_without_mentions,
+        permissions::SETTINGS_MANAGER,
+    },
+    chain::{styles::STYLES, template::validate_template},
+    database::tables::audit::get_setting_changes,
+    t,
+    DatabaseConn,
+};
+
+use super::panel::settings_panel;
+
+/// Exported settings are a few KiB at most, anything much bigger isn't one
+const MAX_IMPORT_SIZE: u64 = 64 * 1024;
 
 command! {
     settings,
@@ -23,7 +68,18 @@ command! {
             optional SubCommand style = get_style | "Get the response style",
             optional SubCommand remove_messages = get_remove | "Get whether we remove chain messages",
             optional SubCommand chain_threshold = get_threshold | "Get the minimum number of messages required for a chain",
-            optional SubCommand alternate = get_alternate | "Get whether you have to alternate to have a valid chain"
+            optional SubCommand alternate = get_alternate | "Get whether you have to alternate to have a valid chain",
+            optional SubCommand match_mode = get_match_mode | "Get how messages are compared to continue a chain",
+            optional SubCommand manager_roles = get_manager_roles | "Get the roles that can change the bot's settings",
+            optional SubCommand log_channel = get_log_channel | "Get the channel changes to the settings are logged in",
+            optional SubCommand template = get_template | "Get the template used by the template style",
+            optional SubCommand react_emoji = get_react_emoji | "Get the emoji the react style uses",
+            optional SubCommand log_responses = get_log_responses | "Get whether chain responses are sent to the log channel",
+            optional SubCommand announce_channel = get_announce_channel | "Get the channel chain results are posted in",
+            optional SubCommand milestones = get_milestones | "Get the chain lengths announced while a chain is running",
+            optional SubCommand locale = get_locale | "Get the language the bot responds in",
+            optional SubCommand ping_participants = get_ping_participants | "Get whether chain responses ping everyone in the chain",
+            optional SubCommand replay_chains = get_replay_chains | "Get whether chains with removed messages are replayed as one message"
         ],
         optional SubCommandGroup set | "Set settings" [
             optional SubCommand prefixes = set_prefix | "Set guild prefixes" [
@@ -36,23 +92,202 @@ command! {
             ],
             optional SubCommand blacklist = set_blacklist | "Flip whether we blacklist or whitelist",
             optional SubCommand style = set_style | "Set the style of chain responses" [
-                required String style | "The new style" {"embed": "embed", "classic": "classic", "text": "text"}
+                required String style | "The new style"
             ],
             optional SubCommand remove_messages = set_remove | "Flip if we remove messages for chains",
             optional SubCommand chain_threshold = set_threshold | "Set the minimum number of messages to make a chain" [
-                required Integer threshold | "The minimum number of messages for a chain"
+                required Integer threshold | "The minimum number of messages for a chain, between 1 and 1000"
+            ],
+            optional SubCommand alternate_messages = set_alternate | "Flip if users need to alternate to make a chain",
+            optional SubCommand match_mode = set_match_mode | "Set how messages are compared to continue a chain" [
+                required String mode | "The new match mode" {"exact": "exact", "ignore_case": "ignore_case"}
+            ],
+            optional SubCommand manager_roles = set_manager_roles | "Set the roles that can change the bot's settings" [
+                required String action | "The action to preform" {"add": "add", "clear": "clear", "remove": "remove"},
+                optional Role role | "The role to add or remove"
+            ],
+            optional SubCommand log_channel = set_log_channel | "Set the channel changes to the settings are logged in" [
+                optional Channel channel | "The log channel, leave empty to stop logging changes"
+            ],
+            optional SubCommand template = set_template | "Set the template used by the template style" [
+                optional String template | "The template, with placeholders like {length} and {breaker}, leave empty to clear it"
+            ],
+            optional SubCommand react_emoji = set_react_emoji | "Set the emoji the react style uses" [
+                optional String emoji | "The emoji, leave empty to react with the chain length"
+            ],
+            optional SubCommand log_responses = set_log_responses | "Flip if chain responses are sent to the log channel instead",
+            optional SubCommand announce_channel = set_announce_channel | "Set the channel chain results are posted in" [
+                optional Channel channel | "The announcement channel, leave empty to post results in the chain's channel"
+            ],
+            optional SubCommand milestones = set_milestones | "Set the chain lengths announced while a chain is running" [
+                required String action | "The action to preform" {"add": "add", "clear": "clear", "remove": "remove"},
+                optional Integer length | "The chain length to add or remove"
+            ],
+            optional SubCommand locale = set_locale | "Set the language the bot responds in" [
+                optional String locale | "The language, leave empty to use the language of Discord"
+            ],
+            optional SubCommand ping_participants = set_ping_participants | "Flip if chain responses ping everyone in the chain",
+            optional SubCommand replay_chains = set_replay_chains | "Flip if chains with removed messages are replayed as one message"
+        ],
+        optional SubCommandGroup channel | "Settings for a channel that override the server settings" [
+            optional SubCommand get = get_channel_settings | "Get a channel's overrides" [
+                required Channel channel | "The channel"
             ],
-            optional SubCommand alternate_messages = set_alternate | "Flip if users need to alternate to make a chain"
+            optional SubCommand set = set_channel_setting | "Override a setting in a channel" [
+                required Channel channel | "The channel",
+                required String setting | "The setting to override" {"chain_threshold": "chain_threshold", "style": "style", "remove_messages": "remove_messages", "alternate_member": "alternate_member", "match_mode": "match_mode"},
+                required String value | "The value to use in the channel"
+            ],
+            optional SubCommand clear = clear_channel_setting | "Remove a channel's overrides" [
+                required Channel channel | "The channel",
+                optional String setting | "The override to remove, defaults to all of them" {"chain_threshold": "chain_threshold", "style": "style", "remove_messages": "remove_messages", "alternate_member": "alternate_member", "match_mode": "match_mode"}
+            ]
+        ],
+        optional SubCommand panel = settings_panel | "Change the settings with buttons and menus",
+        optional SubCommand reset = reset_settings | "Reset the settings to their defaults" [
+            optional String setting | "The setting to reset, defaults to all of them" {
+                "all": "all",
+                "prefixes": "prefixes",
+                "channel_filters": "channel_filters",
+                "blacklist": "blacklist",
+                "style": "style",
+                "remove_messages": "remove_messages",
+                "chain_threshold": "chain_threshold",
+                "alternate_member": "alternate_member",
+                "match_mode": "match_mode",
+                "channel_overrides": "channel_overrides",
+                "manager_roles": "manager_roles",
+                "log_channel": "log_channel",
+                "response_template": "response_template",
+                "react_emoji": "react_emoji",
+                "log_responses": "log_responses",
+                "announce_channel": "announce_channel",
+                "milestones": "milestones",
+                "locale": "locale",
+                "ping_participants": "ping_participants",
+                "replay_chains": "replay_chains"
+            }
+        ],
+        optional SubCommand history = get_history | "See recent changes to the settings" [
+            optional Integer page | "The page of changes"
+        ],
+        optional SubCommand export = export_settings | "Export the server's settings as a file",
+        optional SubCommand import = import_settings | "Import settings from an exported settings file" [
+            optional String message | "The id of the message with the settings file, defaults to your last upload"
         ]
     ]
 }
 
+/// The settings command with the style and locale choices filled in from their registries
+///
+/// `command!` already imports the slashy command types used here
+pub struct SettingsCommand;
+
+impl CommandInit for SettingsCommand {
+    fn command_init() -> Command {
+        let mut command = SETTINGS_COMMAND::command_init();
+        if let Some(children) = &mut command.arguments_tree.children {
+            let styles = STYLES.names().map(|n| (n, n)).collect::<Vec<_>>();
+            add_choices(children, "style", &styles);
+            let locales = LOCALES.iter().map(|l| (l.name, l.code)).collect::<Vec<_>>();
+            add_choices(children, "locale", &locales);
+        }
+        command
+    }
+}
+
+/// Sets the choices of the `setting` option in every `setting` subcommand, as (name, value) pairs
+fn add_choices(
+    arguments: &mut Vec<CommandArguments>,
+    setting: &str,
+    new_choices: &[(&'static str, &'static str)],
+) {
+    for argument in arguments {
+        match argument {
+            CommandArguments::SubCommand {
+                name,
+                options: Some(options),
+                ..
+            } if *name == setting => {
+                let option = options.iter_mut().find(|o| match o {
+                    CommandArguments::String { name, .. } => *name == setting,
+                    _ => false,
+                });
+                if let Some(CommandArguments::String { choices, .. }) = option {
+                    *choices = Some(
+                        new_choices
+                            .iter()
+                            .map(|&(name, value)| ArgumentChoice {
+                                name,
+                                value: value.to_owned(),
+                            })
+                            .collect(),
+                    );
+                }
+            }
+            CommandArguments::SubCommandGroup {
+                options: Some(options),
+                ..
+            } => add_choices(options, setting, new_choices),
+            _ => {}
+        }
+    }
+}
+
 #[subcommand]
 async fn get_settings(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
     let data = ctx.ctx.data.read().await;
     let cache = data.get::<GuildSettingsStore>().unwrap().read().await;
     let settings = cache.get(ctx.guild_id().unwrap()).unwrap();
 
+    let channel = |c: Option<ChannelId>| {
+        c.map_or_else(|| t!(locale, "settings.none"), |c| format!("<#{}>", c.0))
+    };
+
+    let filters = format!("{:?}", settings.channel_filters);
+
+    let fields = vec![
+        (
+            t!(locale, "settings.field.prefixes"),
+            format!("{:?}", settings.prefixes),
+        ),
+        (
+            t!(locale, "settings.field.channel_filters"),
+            if settings.blacklist {
+                t!(locale, "settings.filters_blacklisted", channels = filters)
+            } else {
+                t!(locale, "settings.filters_whitelisted", channels = filters)
+            },
+        ),
+        (t!(locale, "settings.field.style"), settings.style.to_string()),
+        (t!(locale, "settings.field.remove_messages"), settings.remove_messages.to_string()),
+        (t!(locale, "settings.field.chain_threshold"), settings.chain_threshold.to_string()),
+        (t!(locale, "settings.field.alternate_member"), settings.alternate_member.to_string()),
+        (t!(locale, "settings.field.match_mode"), settings.match_mode.to_string()),
+        (
+            t!(locale, "settings.field.channel_overrides"),
+            t!(locale, "settings.channel_count", count = settings.channel_overrides.len()),
+        ),
+        (t!(locale, "settings.field.log_channel"), channel(settings.log_channel)),
+        (
+            t!(locale, "settings.field.react_emoji"),
+            settings
+                .react_emoji
+                .as_deref()
+                .map_or_else(|| t!(locale, "settings.chain_length"), |e| e.to_owned()),
+        ),
+        (t!(locale, "settings.field.log_responses"), settings.log_responses.to_string()),
+        (t!(locale, "settings.field.announce_channel"), channel(settings.announce_channel)),
+        (t!(locale, "settings.field.milestones"), milestone_list(&settings.milestones)),
+        (t!(locale, "settings.field.locale"), locale_name(settings.locale.as_deref(), locale)),
+        (
+            t!(locale, "settings.field.ping_participants"),
+            settings.ping_participants.to_string(),
+        ),
+        (t!(locale, "settings.field.replay_chains"), settings.replay_chains.to_string()),
+    ];
+
     if let Ok(Channel::Guild(c)) = ctx.channel().await {
         if c.permissions_for_user(&ctx.ctx, &ctx.ctx.cache.current_user().await.id)
             .await
@@ -60,88 +295,66 @@ async fn get_settings(ctx: &CommandContext) -> CommandResult {
             .contains(Permissions::EMBED_LINKS)
         {
             ctx.send_embed(|e| {
-                e.title("Settings");
-                e.field("Prefixes", format!("{:?}", settings.prefixes), false);
-                e.field(
-                    "Channel Filters",
-                    format!(
-                        "{:?} are {}",
-                        settings.channel_filters,
-                        if settings.blacklist {
-                            "blacklisted"
-                        } else {
-                            "whitelisted"
-                        }
-                    ),
-                    false,
-                );
-                e.field("Chain Style", settings.style.clone(), false);
-                e.field(
-                    "Remove Chain Messages",
-                    format!("{}", settings.remove_messages),
-                    false,
-                );
-                e.field(
-                    "Chain Threshold",
-                    format!("{}", settings.chain_threshold),
-                    false,
-                );
-                e.field(
-                    "Alternate Members in Chain",
-                    format!("{}", settings.alternate_member),
-                    false,
-                );
+                e.title(t!(locale, "settings.title"));
+                for (name, value) in &fields {
+                    e.field(name, value, false);
+                }
 
                 e
             })
             .await?;
         } else {
-            ctx.send_str(&format!(
-                r#"```
-            Settings for {}
-            -------------------------------
-            Prefixes: {:?}
-            Channel Filters: {:?}
-            Filter Type: {}
-            Chain Style: {}
-            Remove Chain Messages: {}
-            Chain Threshold: {}
-            Alternate Members: {}
-            ```"#,
-                ctx.guild().await?.name,
-                settings.prefixes,
-                settings.channel_filters,
-                if settings.blacklist {
-                    "blacklist"
-                } else {
-                    "whitelist"
-                },
-                settings.style,
-                settings.remove_messages,
-                settings.chain_threshold,
-                settings.alternate_member
-            ))
-            .await?;
+            let content = format!(
+                "```\n{}\n-------------------------------\n{}\n```",
+                t!(
+                    locale,
+                    "settings.title_for",
+                    server = ctx.guild().await?.name
+                ),
+                fields
+                    .iter()
+                    .map(|(name, value)| format!("{}: {}", name, value))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            );
+            send_without_mentions(ctx, &content).await?;
         }
     }
 
     Ok(())
 }
 
+/// The name of a guild's locale setting in the locale responses are sent in
+fn locale_name(setting: Option<&str>, locale: &str) -> String {
+    match LOCALES.iter().find(|l| Some(l.code) == setting) {
+        Some(l) => l.name.to_owned(),
+        None => t!(locale, "settings.locale_auto"),
+    }
+}
+
 #[subcommand]
 async fn get_prefixes(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
     let data = ctx.ctx.data.read().await;
     let settings = data.get::<GuildSettingsStore>().unwrap().read().await;
     let prefixes = settings.prefixes(ctx.guild_id().unwrap());
 
-    ctx.send_str(&format!("The server prefixes are {:?}", prefixes))
-        .await?;
+    send_without_mentions(
+        ctx,
+        &t!(
+            locale,
+            "settings.get.prefixes",
+            prefixes = format!("{:?}", prefixes)
+        ),
+    )
+    .await?;
 
     Ok(())
 }
 
 #[subcommand]
 async fn get_filters(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
     let data = ctx.ctx.data.read().await;
     let settings = data.get::<GuildSettingsStore>().unwrap().read().await;
     let filters = settings
@@ -153,7 +366,7 @@ async fn get_filters(ctx: &CommandContext) -> CommandResult {
     let filters = join_all(filters).await;
     let filters = filters.iter().flatten().collect::<Vec<_>>();
 
-    ctx.send_str(&format!("The channel filter is {:?}", filters))
+    ctx.send_str(&t!(locale, "settings.get.filters", channels = format!("{:?}", filters)))
         .await?;
 
     Ok(())
@@ -161,13 +374,15 @@ async fn get_filters(ctx: &CommandContext) -> CommandResult {
 
 #[subcommand]
 async fn get_blacklist(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
     let data = ctx.ctx.data.read().await;
     let settings = data.get::<GuildSettingsStore>().unwrap().read().await;
     let blacklist = settings.blacklist(ctx.guild_id().unwrap());
-    ctx.send_str(&format!(
-        "The channel filter acts as a {}",
-        if blacklist { "blacklist" } else { "whitelist" }
-    ))
+    ctx.send_str(&if blacklist {
+        t!(locale, "settings.get.blacklist")
+    } else {
+        t!(locale, "settings.get.whitelist")
+    })
     .await?;
 
     Ok(())
@@ -175,10 +390,11 @@ async fn get_blacklist(ctx: &CommandContext) -> CommandResult {
 
 #[subcommand]
 async fn get_style(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
     let data = ctx.ctx.data.read().await;
     let settings = data.get::<GuildSettingsStore>().unwrap().read().await;
     let style = settings.style(ctx.guild_id().unwrap());
-    ctx.send_str(&format!("The chain style is {}", style))
+    ctx.send_str(&t!(locale, "settings.get.style", style = style))
         .await?;
 
     Ok(())
@@ -186,13 +402,15 @@ async fn get_style(ctx: &CommandContext) -> CommandResult {
 
 #[subcommand]
 async fn get_remove(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
     let data = ctx.ctx.data.read().await;
     let settings = data.get::<GuildSettingsStore>().unwrap().read().await;
     let delete = settings.remove_messages(ctx.guild_id().unwrap());
-    ctx.send_str(&format!(
-        "Messages in a chain are{} deleted",
-        if delete { "" } else { " not" }
-    ))
+    ctx.send_str(&if delete {
+        t!(locale, "settings.get.remove_messages")
+    } else {
+        t!(locale, "settings.get.keep_messages")
+    })
     .await?;
 
     Ok(())
@@ -200,27 +418,292 @@ async fn get_remove(ctx: &CommandContext) -> CommandResult {
 
 #[subcommand]
 async fn get_threshold(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
     let data = ctx.ctx.data.read().await;
     let settings = data.get::<GuildSettingsStore>().unwrap().read().await;
     let threshold = settings.chain_threshold(ctx.guild_id().unwrap());
-    ctx.send_str(&format!(
-        "Members need {} messages to form a chain",
-        threshold
-    ))
-    .await?;
+    ctx.send_str(&t!(locale, "settings.get.threshold", threshold = threshold))
+        .await?;
 
     Ok(())
 }
 
 #[subcommand]
 async fn get_alternate(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
     let data = ctx.ctx.data.read().await;
     let settings = data.get::<GuildSettingsStore>().unwrap().read().await;
     let alternate = settings.alternate_member(ctx.guild_id().unwrap());
-    ctx.send_str(&format!(
-        "Members{} need to alternate in order to make a chain",
-        if alternate { "" } else { " do not" }
-    ))
+    ctx.send_str(&if alternate {
+        t!(locale, "settings.get.alternate")
+    } else {
+        t!(locale, "settings.get.no_alternate")
+    })
+    .await?;
+
+    Ok(())
+}
+
+#[subcommand]
+async fn get_match_mode(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let data = ctx.ctx.data.read().await;
+    let settings = data.get::<GuildSettingsStore>().unwrap().read().await;
+    let match_mode = settings.match_mode(ctx.guild_id().unwrap());
+    ctx.send_str(&match match_mode {
+        MatchMode::Exact => t!(locale, "settings.get.match_exact"),
+        MatchMode::IgnoreCase => t!(locale, "settings.get.match_ignore_case"),
+    })
+    .await?;
+
+    Ok(())
+}
+
+#[subcommand]
+async fn get_manager_roles(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let data = ctx.ctx.data.read().await;
+    let settings = data.get::<GuildSettingsStore>().unwrap().read().await;
+    let manager_roles = settings.manager_roles(ctx.guild_id().unwrap());
+
+    if manager_roles.is_empty() {
+        ctx.send_str(&t!(locale, "settings.get.no_manager_roles"))
+            .await?;
+    } else {
+        // Listed by name so the response doesn't ping them
+        let guild = ctx.guild().await?;
+        let roles = manager_roles
+            .iter()
+            .map(|r| match guild.roles.get(r) {
+                Some(role) => format!("@{}", role.name),
+                None => r.0.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        send_without_mentions(
+            ctx,
+            &t!(locale, "settings.get.manager_roles", roles = roles),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+#[subcommand]
+async fn get_log_channel(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let data = ctx.ctx.data.read().await;
+    let settings = data.get::<GuildSettingsStore>().unwrap().read().await;
+
+    match settings.log_channel(ctx.guild_id().unwrap()) {
+        Some(c) =>
+            ctx.send_str(&t!(locale, "settings.get.log_channel", channel = format!("<#{}>", c.0)))
+                .await?,
+        None => ctx.send_str(&t!(locale, "settings.get.no_log_channel")).await?,
+    }
+
+    Ok(())
+}
+
+#[subcommand]
+async fn get_template(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let data = ctx.ctx.data.read().await;
+    let settings = data.get::<GuildSettingsStore>().unwrap().read().await;
+
+    match settings.response_template(ctx.guild_id().unwrap()) {
+        Some(template) =>
+            send_without_mentions(
+                ctx,
+                &t!(locale, "settings.get.template", template = template),
+            )
+            .await?,
+        None => ctx.send_str(&t!(locale, "settings.get.no_template")).await?,
+    }
+
+    Ok(())
+}
+
+#[subcommand]
+async fn get_react_emoji(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let data = ctx.ctx.data.read().await;
+    let settings = data.get::<GuildSettingsStore>().unwrap().read().await;
+
+    match settings.react_emoji(ctx.guild_id().unwrap()) {
+        Some(e) =>
+            send_without_mentions(ctx, &t!(locale, "settings.get.react_emoji", emoji = e))
+                .await?,
+        None => ctx.send_str(&t!(locale, "settings.get.react_length")).await?,
+    }
+
+    Ok(())
+}
+
+#[subcommand]
+async fn get_log_responses(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let data = ctx.ctx.data.read().await;
+    let settings = data.get::<GuildSettingsStore>().unwrap().read().await;
+    let guild_id = ctx.guild_id().unwrap();
+
+    ctx.send_str(
+        &match (settings.log_responses(guild_id), settings.log_channel(guild_id)) {
+            (true, Some(_)) => t!(locale, "settings.get.log_responses"),
+            (true, None) => t!(locale, "settings.get.log_responses_no_channel"),
+            (false, _) => t!(locale, "settings.get.no_log_responses"),
+        },
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[subcommand]
+async fn get_ping_participants(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let data = ctx.ctx.data.read().await;
+    let settings = data.get::<GuildSettingsStore>().unwrap().read().await;
+
+    if settings.ping_participants(ctx.guild_id().unwrap()) {
+        ctx.send_str(&t!(locale, "settings.get.ping_participants")).await?;
+    } else {
+        ctx.send_str(&t!(locale, "settings.get.no_ping_participants")).await?;
+    }
+
+    Ok(())
+}
+
+#[subcommand]
+async fn get_replay_chains(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let data = ctx.ctx.data.read().await;
+    let settings = data.get::<GuildSettingsStore>().unwrap().read().await;
+    let guild_id = ctx.guild_id().unwrap();
+
+    ctx.send_str(
+        &match (settings.replay_chains(guild_id), settings.remove_messages(guild_id)) {
+            (true, true) => t!(locale, "settings.get.replay_chains"),
+            (true, false) => t!(locale, "settings.get.replay_chains_no_remove"),
+            (false, _) => t!(locale, "settings.get.no_replay_chains"),
+        },
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[subcommand]
+async fn get_announce_channel(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let data = ctx.ctx.data.read().await;
+    let settings = data.get::<GuildSettingsStore>().unwrap().read().await;
+
+    match settings.announce_channel(ctx.guild_id().unwrap()) {
+        Some(c) =>
+            ctx.send_str(&t!(
+                locale,
+                "settings.get.announce_channel",
+                channel = format!("<#{}>", c.0)
+            ))
+            .await?,
+        None => ctx.send_str(&t!(locale, "settings.get.no_announce_channel")).await?,
+    }
+
+    Ok(())
+}
+
+#[subcommand]
+async fn get_milestones(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let data = ctx.ctx.data.read().await;
+    let settings = data.get::<GuildSettingsStore>().unwrap().read().await;
+    let milestones = settings.milestones(ctx.guild_id().unwrap());
+
+    if milestones.is_empty() {
+        ctx.send_str(&t!(locale, "settings.get.no_milestones")).await?;
+    } else {
+        ctx.send_str(&t!(
+            locale,
+            "settings.get.milestones",
+            milestones = milestone_list(&milestones)
+        ))
+        .await?;
+    }
+
+    Ok(())
+}
+
+#[subcommand]
+async fn get_locale(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let data = ctx.ctx.data.read().await;
+    let settings = data.get::<GuildSettingsStore>().unwrap().read().await;
+
+    match settings.locale(ctx.guild_id().unwrap()) {
+        Some(l) =>
+            ctx.send_str(&t!(
+                locale,
+                "settings.get.locale",
+                locale = locale_name(Some(&l), locale)
+            ))
+            .await?,
+        None => ctx.send_str(&t!(locale, "settings.get.no_locale")).await?,
+    }
+
+    Ok(())
+}
+
+// Arguments: Optional Integer page
+#[subcommand(SETTINGS_MANAGER)]
+async fn get_history(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let page = *ctx.get_int_arg("page").unwrap_or(&0);
+
+    if page < 0 {
+        ctx.send_str(&t!(locale, "settings.negative_page")).await?;
+        return Ok(());
+    }
+
+    let data = ctx.ctx.data.read().await;
+    let database = data.get::<DatabaseConn>().unwrap().lock().await;
+    let changes = get_setting_changes(&database, ctx.guild_id().unwrap(), page as i64 * 10, 10);
+
+    drop(database);
+
+    let description = if changes.is_empty() {
+        t!(locale, "settings.history.empty")
+    } else {
+        changes
+            .iter()
+            .map(|c| {
+                let changed_at = c
+                    .changed_at
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs();
+
+                t!(
+                    locale,
+                    "settings.history.change",
+                    time = format!("<t:{}:R>", changed_at),
+                    user = format!("<@{}>", c.actor_id.0),
+                    setting = c.field,
+                    old = c.old_value,
+                    new = c.new_value
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    ctx.send_embed(|e| {
+        e.title(t!(locale, "settings.history.title", page = page));
+        e.description(&description);
+        e
+    })
     .await?;
 
     Ok(())
@@ -229,8 +712,9 @@ async fn get_alternate(ctx: &CommandContext) -> CommandResult {
 // Arguments:
 // Optional String prefix
 // String action
-#[subcommand(ADMINISTRATOR)]
+#[subcommand(SETTINGS_MANAGER)]
 async fn set_prefix(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
     let action = ctx.get_str_arg("action").unwrap();
 
     match action.as_str() {
@@ -238,7 +722,7 @@ async fn set_prefix(ctx: &CommandContext) -> CommandResult {
             if ctx.get_str_arg("prefix").is_some() {
                 add_prefix(ctx).await?;
             } else {
-                ctx.send_str("You need to provide a prefix to add").await?;
+                ctx.send_str(&t!(locale, "settings.prefix.missing_add")).await?;
             },
         "reset" => {
             reset_prefix(ctx).await?;
@@ -247,7 +731,7 @@ async fn set_prefix(ctx: &CommandContext) -> CommandResult {
             if ctx.get_str_arg("prefix").is_some() {
                 remove_prefix(ctx).await?;
             } else {
-                ctx.send_str("You need to provide a prefix to remove")
+                ctx.send_str(&t!(locale, "settings.prefix.missing_remove"))
                     .await?;
             },
         _ => unreachable!(),
@@ -258,47 +742,80 @@ async fn set_prefix(ctx: &CommandContext) -> CommandResult {
 
 
 async fn add_prefix(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let prefix = ctx.get_str_arg("prefix").unwrap().clone();
+
+    if let Err(e) = validate_prefixes(&[prefix.clone()]) {
+        send_without_mentions(ctx, &e.localized(locale)).await?;
+        return Ok(());
+    }
+
     let data = ctx.ctx.data.write().await;
     let mut settings = data.get::<GuildSettingsStore>().unwrap().write().await;
     let guild_id = ctx.guild_id().unwrap();
 
-    (*settings.prefixes_mut(guild_id)).push(ctx.get_str_arg("prefix").unwrap().clone());
-
-    ctx.send_str(&format!(
-        "Prefix {} added",
-        ctx.get_str_arg("prefix").unwrap()
-    ))
+    let old = settings.prefixes(guild_id);
+    (*settings.prefixes_mut(guild_id)).push(prefix);
+
+    send_without_mentions(
+        ctx,
+        &t!(
+            locale,
+            "settings.prefix.added",
+            prefix = ctx.get_str_arg("prefix").unwrap()
+        ),
+    )
     .await?;
 
-    settings.save_guild(guild_id);
+    let change = SettingChange::new(
+        "prefixes",
+        old.join(", "),
+        settings.prefixes(guild_id).join(", "),
+    );
+    let entry = audit(ctx, &data, &settings, change).await;
+    drop(settings);
+    drop(data);
+    entry.post(&ctx.ctx).await;
 
     Ok(())
 }
 
 async fn reset_prefix(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
     let data = ctx.ctx.data.write().await;
     let mut settings = data.get::<GuildSettingsStore>().unwrap().write().await;
     let guild_id = ctx.guild_id().unwrap();
 
-    *settings.prefixes_mut(guild_id) = DM_SETTINGS.prefixes.clone();
+    let old = settings.prefixes(guild_id);
+    *settings.prefixes_mut(guild_id) = DEFAULT_SETTINGS.prefixes.clone();
 
-    ctx.send_str("Prefixes reset").await?;
+    ctx.send_str(&t!(locale, "settings.prefix.reset")).await?;
 
-    settings.save_guild(guild_id);
+    let change = SettingChange::new(
+        "prefixes",
+        old.join(", "),
+        DEFAULT_SETTINGS.prefixes.join(", "),
+    );
+    let entry = audit(ctx, &data, &settings, change).await;
+    drop(settings);
+    drop(data);
+    entry.post(&ctx.ctx).await;
 
     Ok(())
 }
 
 async fn remove_prefix(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
     let data = ctx.ctx.data.write().await;
     let mut settings = data.get::<GuildSettingsStore>().unwrap().write().await;
     let guild_id = ctx.guild_id().unwrap();
 
+    let old = settings.prefixes(guild_id);
     let prefixes = settings.prefixes_mut(guild_id);
     let removal = ctx.get_str_arg("prefix").unwrap();
 
     if prefixes.len() == 1 {
-        ctx.send_str("you can't remove a prefix if you only have one")
+        ctx.send_str(&t!(locale, "settings.prefix.last"))
             .await?;
     } else {
         *prefixes = prefixes
@@ -307,9 +824,21 @@ async fn remove_prefix(ctx: &CommandContext) -> CommandResult {
             .map(|p| p.clone())
             .collect::<Vec<String>>();
 
-        ctx.send_str(&format!("Prefix {} removed", removal)).await?;
+        send_without_mentions(
+            ctx,
+            &t!(locale, "settings.prefix.removed", prefix = removal),
+        )
+        .await?;
 
-        settings.save_guild(guild_id);
+        let change = SettingChange::new(
+            "prefixes",
+            old.join(", "),
+            settings.prefixes(guild_id).join(", "),
+        );
+        let entry = audit(ctx, &data, &settings, change).await;
+        drop(settings);
+        drop(data);
+        entry.post(&ctx.ctx).await;
     }
 
     Ok(())
@@ -317,8 +846,9 @@ async fn remove_prefix(ctx: &CommandContext) -> CommandResult {
 
 // Arguments:
 // Channel channel_id
-#[subcommand(ADMINISTRATOR)]
+#[subcommand(SETTINGS_MANAGER)]
 async fn set_filters(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
     let action = ctx.get_str_arg("action").unwrap();
 
     match action.as_str() {
@@ -326,7 +856,7 @@ async fn set_filters(ctx: &CommandContext) -> CommandResult {
             if ctx.get_str_arg("channel_id").is_some() {
                 add_filter(ctx).await?;
             } else {
-                ctx.send_str("You need to provide a channel id to add")
+                ctx.send_str(&t!(locale, "settings.filter.missing_add"))
                     .await?;
             },
         "clear" => {
@@ -336,7 +866,7 @@ async fn set_filters(ctx: &CommandContext) -> CommandResult {
             if ctx.get_str_arg("channel_id").is_some() {
                 remove_filter(ctx).await?;
             } else {
-                ctx.send_str("You need to provide a channel id to remove")
+                ctx.send_str(&t!(locale, "settings.filter.missing_remove"))
                     .await?;
             },
         _ => unreachable!(),
@@ -347,39 +877,57 @@ async fn set_filters(ctx: &CommandContext) -> CommandResult {
 
 
 async fn add_filter(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
     let data = ctx.ctx.data.write().await;
     let mut settings = data.get::<GuildSettingsStore>().unwrap().write().await;
     let guild_id = ctx.guild_id().unwrap();
 
+    let old = settings.channel_filters(guild_id);
     (*settings.channel_filters_mut(guild_id))
         .push(ctx.get_channel_arg("channel_id").unwrap().clone());
 
-    ctx.send_str("Added new channel filter").await?;
+    ctx.send_str(&t!(locale, "settings.filter.added")).await?;
 
-    settings.save_guild(guild_id);
+    let change = SettingChange::new(
+        "channel_filters",
+        channel_list(&old),
+        channel_list(&settings.channel_filters(guild_id)),
+    );
+    let entry = audit(ctx, &data, &settings, change).await;
+    drop(settings);
+    drop(data);
+    entry.post(&ctx.ctx).await;
 
     Ok(())
 }
 
 async fn clear_filters(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
     let data = ctx.ctx.data.write().await;
     let mut settings = data.get::<GuildSettingsStore>().unwrap().write().await;
     let guild_id = ctx.guild_id().unwrap();
 
+    let old = settings.channel_filters(guild_id);
     *settings.channel_filters_mut(guild_id) = Vec::new();
 
-    ctx.send_str("Cleared channel filters").await?;
+    ctx.send_str(&t!(locale, "settings.filter.cleared")).await?;
 
-    settings.save_guild(guild_id);
+    let change = SettingChange::new("channel_filters", channel_list(&old), "");
+    let entry = audit(ctx, &data, &settings, change).await;
+    drop(settings);
+    drop(data);
+    entry.post(&ctx.ctx).await;
 
     Ok(())
 }
 
 async fn remove_filter(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
     let data = ctx.ctx.data.write().await;
     let mut settings = data.get::<GuildSettingsStore>().unwrap().write().await;
     let guild_id = ctx.guild_id().unwrap();
 
+    let old = settings.channel_filters(guild_id);
     let channels = settings.channel_filters_mut(guild_id);
     let removal = ctx.get_channel_arg("channel_id").unwrap();
 
@@ -389,97 +937,1085 @@ async fn remove_filter(ctx: &CommandContext) -> CommandResult {
         .map(|p| *p)
         .collect::<Vec<_>>();
 
-    ctx.send_str("Removed channel filter").await?;
+    ctx.send_str(&t!(locale, "settings.filter.removed")).await?;
 
-    settings.save_guild(guild_id);
+    let change = SettingChange::new(
+        "channel_filters",
+        channel_list(&old),
+        channel_list(&settings.channel_filters(guild_id)),
+    );
+    let entry = audit(ctx, &data, &settings, change).await;
+    drop(settings);
+    drop(data);
+    entry.post(&ctx.ctx).await;
 
     Ok(())
 }
 
 
-#[subcommand(ADMINISTRATOR)]
+#[subcommand(SETTINGS_MANAGER)]
 async fn set_blacklist(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
     let data = ctx.ctx.data.write().await;
     let mut settings = data.get::<GuildSettingsStore>().unwrap().write().await;
     let guild_id = ctx.guild_id().unwrap();
 
     *settings.blacklist_mut(guild_id) ^= true;
+    let new = settings.blacklist(guild_id);
 
-    ctx.send_str("Flipped if we are blacklisting or whitelisting")
+    ctx.send_str(&t!(locale, "settings.set.blacklist"))
         .await?;
 
-    settings.save_guild(guild_id);
+    let change = SettingChange::new("blacklist", !new, new);
+    let entry = audit(ctx, &data, &settings, change).await;
+    drop(settings);
+    drop(data);
+    entry.post(&ctx.ctx).await;
 
     Ok(())
 }
 
-#[subcommand(ADMINISTRATOR)]
+#[subcommand(SETTINGS_MANAGER)]
 async fn set_remove(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
     let data = ctx.ctx.data.write().await;
     let mut settings = data.get::<GuildSettingsStore>().unwrap().write().await;
     let guild_id = ctx.guild_id().unwrap();
 
     *settings.remove_messages_mut(guild_id) ^= true;
+    let new = settings.remove_messages(guild_id);
 
-    ctx.send_str("Flipped if we remove chain messages").await?;
+    ctx.send_str(&t!(locale, "settings.set.remove_messages")).await?;
 
-    settings.save_guild(guild_id);
+    let change = SettingChange::new("remove_messages", !new, new);
+    let entry = audit(ctx, &data, &settings, change).await;
+    drop(settings);
+    drop(data);
+    entry.post(&ctx.ctx).await;
 
     Ok(())
 }
 
 // Arguments: Int threshold
-#[subcommand(ADMINISTRATOR)]
+#[subcommand(SETTINGS_MANAGER)]
 async fn set_threshold(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let threshold = match parse_threshold(*ctx.get_int_arg("threshold").unwrap() as i64) {
+        Ok(t) => t,
+        Err(e) => {
+            send_without_mentions(ctx, &e.localized(locale)).await?;
+            return Ok(());
+        }
+    };
+
     let data = ctx.ctx.data.write().await;
     let mut settings = data.get::<GuildSettingsStore>().unwrap().write().await;
     let guild_id = ctx.guild_id().unwrap();
 
-    *settings.chain_threshold_mut(guild_id) = ctx.get_int_arg("threshold").unwrap().clone() as u16;
+    let old = settings.chain_threshold(guild_id);
+    *settings.chain_threshold_mut(guild_id) = threshold;
 
-    ctx.send_str(&format!(
-        "Set the threshold to {}",
-        ctx.get_int_arg("threshold").unwrap()
-    ))
-    .await?;
+    ctx.send_str(&t!(locale, "settings.set.threshold", threshold = threshold))
+        .await?;
 
-    settings.save_guild(guild_id);
+    let change = SettingChange::new("chain_threshold", old, threshold);
+    let entry = audit(ctx, &data, &settings, change).await;
+    drop(settings);
+    drop(data);
+    entry.post(&ctx.ctx).await;
 
     Ok(())
 }
 
-#[subcommand(ADMINISTRATOR)]
+#[subcommand(SETTINGS_MANAGER)]
 async fn set_alternate(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
     let data = ctx.ctx.data.write().await;
     let mut settings = data.get::<GuildSettingsStore>().unwrap().write().await;
     let guild_id = ctx.guild_id().unwrap();
 
     *settings.alternate_member_mut(guild_id) ^= true;
+    let new = settings.alternate_member(guild_id);
 
-    ctx.send_str("Flipped if members need to alternate to chain")
+    ctx.send_str(&t!(locale, "settings.set.alternate"))
         .await?;
 
-    settings.save_guild(guild_id);
+    let change = SettingChange::new("alternate_member", !new, new);
+    let entry = audit(ctx, &data, &settings, change).await;
+    drop(settings);
+    drop(data);
+    entry.post(&ctx.ctx).await;
 
     Ok(())
 }
 
 // Arguments: String style
-#[subcommand(ADMINISTRATOR)]
+#[subcommand(SETTINGS_MANAGER)]
 async fn set_style(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let style = match ctx.get_str_arg("style").unwrap().parse::<StyleName>() {
+        Ok(s) => s,
+        Err(e) => {
+            send_without_mentions(ctx, &e.localized(locale)).await?;
+            return Ok(());
+        }
+    };
+
+    let data = ctx.ctx.data.write().await;
+    let mut settings = data.get::<GuildSettingsStore>().unwrap().write().await;
+    let guild_id = ctx.guild_id().unwrap();
+
+    let old = settings.style(guild_id);
+    *settings.style_mut(guild_id) = style;
+
+    ctx.send_str(&t!(locale, "settings.set.style", style = style))
+        .await?;
+
+    let change = SettingChange::new("style", old, style);
+    let entry = audit(ctx, &data, &settings, change).await;
+    drop(settings);
+    drop(data);
+    entry.post(&ctx.ctx).await;
+
+    Ok(())
+}
+
+// Arguments: String mode
+#[subcommand(SETTINGS_MANAGER)]
+async fn set_match_mode(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let match_mode = match ctx.get_str_arg("mode").unwrap().parse::<MatchMode>() {
+        Ok(m) => m,
+        Err(e) => {
+            send_without_mentions(ctx, &e.localized(locale)).await?;
+            return Ok(());
+        }
+    };
+
+    let data = ctx.ctx.data.write().await;
+    let mut settings = data.get::<GuildSettingsStore>().unwrap().write().await;
+    let guild_id = ctx.guild_id().unwrap();
+
+    let old = settings.match_mode(guild_id);
+    *settings.match_mode_mut(guild_id) = match_mode;
+
+    ctx.send_str(&t!(locale, "settings.set.match_mode", mode = match_mode))
+        .await?;
+
+    let change = SettingChange::new("match_mode", old, match_mode);
+    let entry = audit(ctx, &data, &settings, change).await;
+    drop(settings);
+    drop(data);
+    entry.post(&ctx.ctx).await;
+
+    Ok(())
+}
+
+// Arguments:
+// String action
+// Optional Role role
+// Only administrators can choose who manages the bot so managers can't give themselves more access
+#[subcommand(ADMINISTRATOR)]
+async fn set_manager_roles(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let action = ctx.get_str_arg("action").unwrap();
+    let role = ctx.get_role_arg("role").copied();
+
+    if action != "clear" && role.is_none() {
+        ctx.send_str(&t!(locale, "settings.manager_roles.missing")).await?;
+        return Ok(());
+    }
+
+    let data = ctx.ctx.data.write().await;
+    let mut settings = data.get::<GuildSettingsStore>().unwrap().write().await;
+    let guild_id = ctx.guild_id().unwrap();
+
+    let old = settings.manager_roles(guild_id);
+    let manager_roles = settings.manager_roles_mut(guild_id);
+
+    match action.as_str() {
+        "add" => {
+            let role = role.unwrap();
+            if !manager_roles.contains(&role) {
+                manager_roles.push(role);
+            }
+            ctx.send_str(&t!(locale, "settings.manager_roles.added")).await?;
+        }
+        "remove" => {
+            let role = role.unwrap();
+            manager_roles.retain(|r| *r != role);
+            ctx.send_str(&t!(locale, "settings.manager_roles.removed")).await?;
+        }
+        "clear" => {
+            manager_roles.clear();
+            ctx.send_str(&t!(locale, "settings.manager_roles.cleared")).await?;
+        }
+        _ => unreachable!(),
+    }
+
+    let change = SettingChange::new(
+        "manager_roles",
+        role_list(&old),
+        role_list(&settings.manager_roles(guild_id)),
+    );
+    let entry = audit(ctx, &data, &settings, change).await;
+    drop(settings);
+    drop(data);
+    entry.post(&ctx.ctx).await;
+
+    Ok(())
+}
+
+// Arguments: Optional Channel channel
+#[subcommand(SETTINGS_MANAGER)]
+async fn set_log_channel(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let channel = ctx.get_channel_arg("channel").copied();
+    let guild_id = ctx.guild_id().unwrap();
+
+    if let Some(c) = channel {
+        if !guild_id.channels(&ctx.ctx).await?.contains_key(&c) {
+            ctx.send_str(&t!(locale, "settings.channel_not_in_server")).await?;
+            return Ok(());
+        }
+    }
+
+    let data = ctx.ctx.data.write().await;
+    let mut settings = data.get::<GuildSettingsStore>().unwrap().write().await;
+
+    let old = settings.log_channel(guild_id);
+    *settings.log_channel_mut(guild_id) = channel;
+
+    match channel {
+        Some(c) =>
+            ctx.send_str(&t!(locale, "settings.set.log_channel", channel = format!("<#{}>", c.0)))
+                .await?,
+        None => ctx.send_str(&t!(locale, "settings.set.no_log_channel")).await?,
+    }
+
+    let change = SettingChange::new(
+        "log_channel",
+        channel_list(&old.into_iter().collect::<Vec<_>>()),
+        channel_list(&channel.into_iter().collect::<Vec<_>>()),
+    );
+    // Posted in the old log channel too so it shows where the log went
+    let entry = audit(ctx, &data, &settings, change).await.also_post_in(old);
+    drop(settings);
+    drop(data);
+    entry.post(&ctx.ctx).await;
+
+    Ok(())
+}
+
+// Arguments: Optional String template
+#[subcommand(SETTINGS_MANAGER)]
+async fn set_template(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let template = ctx.get_str_arg("template").cloned();
+
+    if let Some(t) = &template {
+        if let Err(e) = validate_template(t) {
+            send_without_mentions(ctx, &SettingsError::InvalidTemplate(e).localized(locale))
+                .await?;
+            return Ok(());
+        }
+    }
+
     let data = ctx.ctx.data.write().await;
     let mut settings = data.get::<GuildSettingsStore>().unwrap().write().await;
     let guild_id = ctx.guild_id().unwrap();
 
-    *settings.style_mut(guild_id) = ctx.get_str_arg("style").unwrap().clone();
+    let old = settings.response_template(guild_id);
+    *settings.response_template_mut(guild_id) = template.clone();
+
+    ctx.send_str(&match (&template, settings.style(guild_id)) {
+        (Some(_), style) if style.name() == "template" => t!(locale, "settings.set.template"),
+        (Some(_), _) => t!(locale, "settings.set.template_unused"),
+        (None, _) => t!(locale, "settings.set.no_template"),
+    })
+    .await?;
+
+    let change = SettingChange::new(
+        "response_template",
+        old.unwrap_or_default(),
+        template.unwrap_or_default(),
+    );
+    let entry = audit(ctx, &data, &settings, change).await;
+    drop(settings);
+    drop(data);
+    entry.post(&ctx.ctx).await;
+
+    Ok(())
+}
+
+// Arguments: Optional Channel channel
+#[subcommand(SETTINGS_MANAGER)]
+async fn set_announce_channel(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let channel = ctx.get_channel_arg("channel").copied();
+    let guild_id = ctx.guild_id().unwrap();
+
+    if let Some(c) = channel {
+        if !guild_id.channels(&ctx.ctx).await?.contains_key(&c) {
+            ctx.send_str(&t!(locale, "settings.channel_not_in_server")).await?;
+            return Ok(());
+        }
+    }
+
+    let data = ctx.ctx.data.write().await;
+    let mut settings = data.get::<GuildSettingsStore>().unwrap().write().await;
+
+    let old = settings.announce_channel(guild_id);
+    *settings.announce_channel_mut(guild_id) = channel;
 
-    ctx.send_str(&format!(
-        "Set the chain style to {}",
-        ctx.get_str_arg("style").unwrap()
+    match channel {
+        Some(c) =>
+            ctx.send_str(&t!(
+                locale,
+                "settings.set.announce_channel",
+                channel = format!("<#{}>", c.0)
+            ))
+            .await?,
+        None =>
+            ctx.send_str(&t!(locale, "settings.set.no_announce_channel"))
+                .await?,
+    }
+
+    let change = SettingChange::new(
+        "announce_channel",
+        channel_list(&old.into_iter().collect::<Vec<_>>()),
+        channel_list(&channel.into_iter().collect::<Vec<_>>()),
+    );
+    let entry = audit(ctx, &data, &settings, change).await;
+    drop(settings);
+    drop(data);
+    entry.post(&ctx.ctx).await;
+
+    Ok(())
+}
+
+// Arguments:
+// String action
+// Optional Integer length
+#[subcommand(SETTINGS_MANAGER)]
+async fn set_milestones(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let action = ctx.get_str_arg("action").unwrap();
+    let length = match (action.as_str(), ctx.get_int_arg("length")) {
+        ("clear", _) => None,
+        (_, Some(l)) => match parse_milestone(*l as i64) {
+            Ok(l) => Some(l),
+            Err(e) => {
+                send_without_mentions(ctx, &e.localized(locale)).await?;
+                return Ok(());
+            }
+        },
+        (_, None) => {
+            ctx.send_str(&t!(locale, "settings.milestones.missing", action = action))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let data = ctx.ctx.data.write().await;
+    let mut settings = data.get::<GuildSettingsStore>().unwrap().write().await;
+    let guild_id = ctx.guild_id().unwrap();
+
+    let old = settings.milestones(guild_id);
+    let milestones = settings.milestones_mut(guild_id);
+
+    match (action.as_str(), length) {
+        ("clear", _) => {
+            milestones.clear();
+            ctx.send_str(&t!(locale, "settings.milestones.cleared")).await?;
+        }
+        ("add", Some(l)) => {
+            if milestones.contains(&l) {
+                ctx.send_str(&t!(locale, "settings.milestones.exists", length = l)).await?;
+                return Ok(());
+            }
+
+            milestones.push(l);
+            milestones.sort_unstable();
+
+            if let Err(e) = validate_milestones(milestones) {
+                *milestones = old;
+                send_without_mentions(ctx, &e.localized(locale)).await?;
+                return Ok(());
+            }
+
+            ctx.send_str(&t!(locale, "settings.milestones.added", length = l)).await?;
+        }
+        ("remove", Some(l)) => {
+            if !milestones.contains(&l) {
+                ctx.send_str(&t!(locale, "settings.milestones.missing_length", length = l))
+                    .await?;
+                return Ok(());
+            }
+
+            milestones.retain(|m| *m != l);
+            ctx.send_str(&t!(locale, "settings.milestones.removed", length = l)).await?;
+        }
+        _ => unreachable!(),
+    }
+
+    let change = SettingChange::new(
+        "milestones",
+        milestone_list(&old),
+        milestone_list(&settings.milestones(guild_id)),
+    );
+    let entry = audit(ctx, &data, &settings, change).await;
+    drop(settings);
+    drop(data);
+    entry.post(&ctx.ctx).await;
+
+    Ok(())
+}
+
+/// Records a change made by the command's author
+///
+/// The entry is returned to be posted once the command has dropped its locks
+async fn audit(
+    ctx: &CommandContext,
+    data: &TypeMap,
+    settings: &GuildSettingsCache,
+    change: SettingChange,
+) -> AuditEntry {
+    record_change(data, settings, ctx.guild_id().unwrap(), ctx.author().unwrap().id, change).await
+}
+
+fn milestone_list(milestones: &[u16]) -> String {
+    milestones
+        .iter()
+        .map(|m| m.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// Arguments: Optional String emoji
+#[subcommand(SETTINGS_MANAGER)]
+async fn set_react_emoji(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let emoji = ctx.get_str_arg("emoji").map(|e| e.trim().to_owned());
+
+    if let Some(e) = &emoji {
+        if let Err(e) = validate_react_emoji(e) {
+            send_without_mentions(ctx, &e.localized(locale)).await?;
+            return Ok(());
+        }
+    }
+
+    let data = ctx.ctx.data.write().await;
+    let mut settings = data.get::<GuildSettingsStore>().unwrap().write().await;
+    let guild_id = ctx.guild_id().unwrap();
+
+    let old = settings.react_emoji(guild_id);
+    *settings.react_emoji_mut(guild_id) = emoji.clone();
+
+    match &emoji {
+        Some(e) =>
+            send_without_mentions(ctx, &t!(locale, "settings.set.react_emoji", emoji = e))
+                .await?,
+        None => ctx.send_str(&t!(locale, "settings.set.react_length")).await?,
+    }
+
+    let change = SettingChange::new(
+        "react_emoji",
+        old.unwrap_or_default(),
+        emoji.unwrap_or_default(),
+    );
+    let entry = audit(ctx, &data, &settings, change).await;
+    drop(settings);
+    drop(data);
+    entry.post(&ctx.ctx).await;
+
+    Ok(())
+}
+
+#[subcommand(SETTINGS_MANAGER)]
+async fn set_log_responses(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let data = ctx.ctx.data.write().await;
+    let mut settings = data.get::<GuildSettingsStore>().unwrap().write().await;
+    let guild_id = ctx.guild_id().unwrap();
+
+    *settings.log_responses_mut(guild_id) ^= true;
+    let new = settings.log_responses(guild_id);
+
+    ctx.send_str(&match (new, settings.log_channel(guild_id)) {
+        (true, Some(_)) => t!(locale, "settings.set.log_responses"),
+        (true, None) => t!(locale, "settings.set.log_responses_no_channel"),
+        (false, _) => t!(locale, "settings.set.no_log_responses"),
+    })
+    .await?;
+
+    let change = SettingChange::new("log_responses", !new, new);
+    let entry = audit(ctx, &data, &settings, change).await;
+    drop(settings);
+    drop(data);
+    entry.post(&ctx.ctx).await;
+
+    Ok(())
+}
+
+#[subcommand(SETTINGS_MANAGER)]
+async fn set_ping_participants(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let data = ctx.ctx.data.write().await;
+    let mut settings = data.get::<GuildSettingsStore>().unwrap().write().await;
+    let guild_id = ctx.guild_id().unwrap();
+
+    *settings.ping_participants_mut(guild_id) ^= true;
+    let new = settings.ping_participants(guild_id);
+
+    if new {
+        ctx.send_str(&t!(locale, "settings.set.ping_participants")).await?;
+    } else {
+        ctx.send_str(&t!(locale, "settings.set.no_ping_participants")).await?;
+    }
+
+    let change = SettingChange::new("ping_participants", !new, new);
+    let entry = audit(ctx, &data, &settings, change).await;
+    drop(settings);
+    drop(data);
+    entry.post(&ctx.ctx).await;
+
+    Ok(())
+}
+
+#[subcommand(SETTINGS_MANAGER)]
+async fn set_replay_chains(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let data = ctx.ctx.data.write().await;
+    let mut settings = data.get::<GuildSettingsStore>().unwrap().write().await;
+    let guild_id = ctx.guild_id().unwrap();
+
+    *settings.replay_chains_mut(guild_id) ^= true;
+    let new = settings.replay_chains(guild_id);
+
+    ctx.send_str(&match (new, settings.remove_messages(guild_id)) {
+        (true, true) => t!(locale, "settings.set.replay_chains"),
+        (true, false) => t!(locale, "settings.set.replay_chains_no_remove"),
+        (false, _) => t!(locale, "settings.set.no_replay_chains"),
+    })
+    .await?;
+
+    let change = SettingChange::new("replay_chains", !new, new);
+    let entry = audit(ctx, &data, &settings, change).await;
+    drop(settings);
+    drop(data);
+    entry.post(&ctx.ctx).await;
+
+    Ok(())
+}
+
+// Arguments: Optional String locale
+#[subcommand(SETTINGS_MANAGER)]
+async fn set_locale(ctx: &CommandContext) -> CommandResult {
+    let new = ctx.get_str_arg("locale").cloned();
+
+    if let Some(l) = &new {
+        if let Err(e) = validate_locale(l) {
+            let locale = command_locale(ctx).await;
+            send_without_mentions(ctx, &e.localized(locale)).await?;
+            return Ok(());
+        }
+    }
+
+    let data = ctx.ctx.data.write().await;
+    let mut settings = data.get::<GuildSettingsStore>().unwrap().write().await;
+    let guild_id = ctx.guild_id().unwrap();
+
+    let old = settings.locale(guild_id);
+    *settings.locale_mut(guild_id) = new.clone();
+
+    let change = SettingChange::new(
+        "locale",
+        old.unwrap_or_default(),
+        new.clone().unwrap_or_default(),
+    );
+    let entry = audit(ctx, &data, &settings, change).await;
+    drop(settings);
+    drop(data);
+    entry.post(&ctx.ctx).await;
+
+    // Answer in the new language so it's clear the change worked
+    let locale = command_locale(ctx).await;
+    match &new {
+        Some(l) =>
+            ctx.send_str(&t!(locale, "settings.set.locale", locale = locale_name(Some(l), locale)))
+                .await?,
+        None => ctx.send_str(&t!(locale, "settings.set.no_locale")).await?,
+    }
+
+    Ok(())
+}
+
+// Arguments: Channel channel
+#[subcommand]
+async fn get_channel_settings(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let data = ctx.ctx.data.read().await;
+    let settings = data.get::<GuildSettingsStore>().unwrap().read().await;
+    let channel = *ctx.get_channel_arg("channel").unwrap();
+    let overrides = settings
+        .channel_overrides(ctx.guild_id().unwrap())
+        .remove(&channel)
+        .unwrap_or_default();
+
+    drop(settings);
+
+    if overrides.is_empty() {
+        ctx.send_str(&t!(
+            locale,
+            "settings.channel.no_overrides",
+            channel = format!("<#{}>", channel.0)
+        ))
+        .await?;
+        return Ok(());
+    }
+
+    let mut lines = Vec::new();
+    if let Some(threshold) = overrides.chain_threshold {
+        lines.push(format!("{}: {}", t!(locale, "settings.field.chain_threshold"), threshold));
+    }
+    if let Some(style) = overrides.style {
+        lines.push(format!("{}: {}", t!(locale, "settings.field.style"), style));
+    }
+    if let Some(remove) = overrides.remove_messages {
+        lines.push(format!("{}: {}", t!(locale, "settings.field.remove_messages"), remove));
+    }
+    if let Some(alternate) = overrides.alternate_member {
+        lines.push(format!("{}: {}", t!(locale, "settings.field.alternate_member"), alternate));
+    }
+    if let Some(match_mode) = overrides.match_mode {
+        lines.push(format!("{}: {}", t!(locale, "settings.field.match_mode"), match_mode));
+    }
+
+    ctx.send_str(&t!(
+        locale,
+        "settings.channel.overrides",
+        channel = format!("<#{}>", channel.0),
+        overrides = lines.join("\n")
     ))
     .await?;
 
-    settings.save_guild(guild_id);
+    Ok(())
+}
+
+// Arguments:
+// Channel channel
+// String setting
+// String value
+#[subcommand(SETTINGS_MANAGER)]
+async fn set_channel_setting(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let channel = *ctx.get_channel_arg("channel").unwrap();
+    let setting = ctx.get_str_arg("setting").unwrap();
+    let value = ctx.get_str_arg("value").unwrap();
+    let guild_id = ctx.guild_id().unwrap();
+
+    if !guild_id.channels(&ctx.ctx).await?.contains_key(&channel) {
+        ctx.send_str(&t!(locale, "settings.channel_not_in_server")).await?;
+        return Ok(());
+    }
+
+    let data = ctx.ctx.data.write().await;
+    let mut settings = data.get::<GuildSettingsStore>().unwrap().write().await;
+
+    let overrides = settings.channel_overrides_mut(guild_id).entry(channel).or_default();
+    let old = overrides.get(setting);
+    let result = overrides.set(setting, value);
+
+    match result {
+        Ok(_) => {
+            ctx.send_str(&t!(
+                locale,
+                "settings.channel.set",
+                setting = setting,
+                value = value,
+                channel = format!("<#{}>", channel.0)
+            ))
+            .await?;
+
+            let change = SettingChange::new(
+                channel_setting_label(locale, setting, channel),
+                old.unwrap_or_else(|| t!(locale, "audit.server_setting")),
+                value,
+            );
+            let entry = audit(ctx, &data, &settings, change).await;
+            drop(settings);
+            drop(data);
+            entry.post(&ctx.ctx).await;
+        }
+        Err(e) => {
+            send_without_mentions(ctx, &e.localized(locale)).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// How a channel override is named in the audit log
+fn channel_setting_label(locale: &str, setting: &str, channel: ChannelId) -> String {
+    t!(
+        locale,
+        "audit.channel_setting",
+        setting = setting,
+        channel = format!("<#{}>", channel.0)
+    )
+}
+
+// Arguments:
+// Channel channel
+// Optional String setting
+#[subcommand(SETTINGS_MANAGER)]
+async fn clear_channel_setting(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let data = ctx.ctx.data.write().await;
+    let mut settings = data.get::<GuildSettingsStore>().unwrap().write().await;
+    let guild_id = ctx.guild_id().unwrap();
+    let channel = *ctx.get_channel_arg("channel").unwrap();
+
+    let overrides = settings.channel_overrides_mut(guild_id);
+
+    let change = match ctx.get_str_arg("setting") {
+        Some(setting) => {
+            let mut old = None;
+
+            if let Some(channel_overrides) = overrides.get_mut(&channel) {
+                old = channel_overrides.get(setting);
+                channel_overrides.clear(setting)?;
+
+                if channel_overrides.is_empty() {
+                    overrides.remove(&channel);
+                }
+            }
+
+            ctx.send_str(&t!(
+                locale,
+                "settings.channel.cleared",
+                channel = format!("<#{}>", channel.0),
+                setting = setting
+            ))
+            .await?;
+
+            SettingChange::new(
+                channel_setting_label(locale, setting, channel),
+                old.unwrap_or_else(|| t!(locale, "audit.server_setting")),
+                t!(locale, "audit.server_setting"),
+            )
+        }
+        None => {
+            overrides.remove(&channel);
+
+            ctx.send_str(&t!(
+                locale,
+                "settings.channel.cleared_all",
+                channel = format!("<#{}>", channel.0)
+            ))
+            .await?;
+
+            SettingChange::new(
+                t!(locale, "audit.channel_overrides", channel = format!("<#{}>", channel.0)),
+                "-",
+                t!(locale, "audit.server_settings"),
+            )
+        }
+    };
+
+    let entry = audit(ctx, &data, &settings, change).await;
+    drop(settings);
+    drop(data);
+    entry.post(&ctx.ctx).await;
+
+    Ok(())
+}
+
+// Arguments: Optional String setting
+#[subcommand(SETTINGS_MANAGER)]
+async fn reset_settings(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let setting = ctx.get_str_arg("setting").map_or("all", |s| s.as_str());
+    let author_id = ctx.author().unwrap().id;
+
+    // Only administrators can choose who manages the bot
+    let is_admin = ctx
+        .member()
+        .await?
+        .permissions(&ctx.ctx)
+        .await?
+        .administrator();
+
+    if setting == "manager_roles" && !is_admin {
+        ctx.send_str(&t!(locale, "settings.reset.manager_roles"))
+            .await?;
+        return Ok(());
+    }
+
+    ctx.send_message(|m| {
+        m.content(if setting == "all" {
+            t!(locale, "settings.reset.confirm_all")
+        } else {
+            t!(locale, "settings.reset.confirm", setting = setting)
+        });
+        m.components(|c| {
+            c.create_action_row(|r| {
+                r.create_button(|b| {
+                    b.style(ButtonStyle::Danger)
+                        .label(t!(locale, "settings.reset.button"))
+                        .custom_id(format!("settings:reset:{}:{}", setting, author_id))
+                });
+                r.create_button(|b| {
+                    b.style(ButtonStyle::Secondary)
+                        .label(t!(locale, "settings.reset.cancel_button"))
+                        .custom_id(format!("settings:reset:cancel:{}", author_id))
+                })
+            })
+        });
+        m.allowed_mentions(|a| a.empty_parse());
+        m
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Handles the confirmation buttons sent by `/settings reset`
+///
+/// `args` are the setting to reset, or `cancel`, and the user who asked for the reset
+pub async fn reset_component(
+    ctx: &Context,
+    interaction: &MessageComponentInteraction,
+    guild_id: GuildId,
+    args: &[&str],
+) -> CommandResult {
+    let data = ctx.data.read().await;
+    let locale_setting = data.get::<GuildSettingsStore>().unwrap().read().await.locale(guild_id);
+    let locale = interaction_locale(locale_setting.as_deref(), &interaction.locale);
+    drop(data);
+
+    // Only the user who asked can confirm the reset
+    if args.get(1) != Some(&interaction.user.id.to_string().as_str()) {
+        return respond_ephemeral(ctx, interaction, &t!(locale, "settings.reset.not_yours"))
+            .await;
+    }
+
+    let setting = args[0];
+
+    let content = if setting == "cancel" {
+        t!(locale, "settings.reset.cancelled")
+    } else {
+        let is_admin = match &interaction.member {
+            Some(m) => m.permissions(ctx).await?.administrator(),
+            None => false,
+        };
+
+        let data = ctx.data.write().await;
+        let mut settings = data.get::<GuildSettingsStore>().unwrap().write().await;
+
+        let old = settings.get_or_default(guild_id);
+        let guild_settings = settings.get_mut_or_default(guild_id);
+
+        if let Err(e) = guild_settings.reset(setting) {
+            return respond_ephemeral(ctx, interaction, &e.localized(locale)).await;
+        }
+
+        // Non administrators can't change who manages the bot, even by resetting everything
+        if !is_admin {
+            guild_settings.manager_roles = old.manager_roles.clone();
+        }
+
+        let change = if setting == "all" {
+            SettingChange::new(
+                t!(locale, "audit.all_settings"),
+                "-",
+                t!(locale, "audit.defaults"),
+            )
+        } else {
+            let old = serde_json::to_value(&old)?;
+            let new = serde_json::to_value(&*guild_settings)?;
+            SettingChange::new(setting, &old[setting], &new[setting])
+        };
+
+        // A confirmed reset is saved straight away instead of waiting for the next flush
+        settings.flush_guild(guild_id);
+
+        let entry = record_change(&data, &settings, guild_id, interaction.user.id, change).await;
+        drop(settings);
+        drop(data);
+        entry.post(ctx).await;
+
+        if setting == "all" {
+            t!(locale, "settings.reset.done_all")
+        } else {
+            t!(locale, "settings.reset.done", setting = setting)
+        }
+    };
+
+    interaction
+        .create_interaction_response(ctx, |r| {
+            r.kind(InteractionResponseType::UpdateMessage);
+            r.interaction_response_data(|d| {
+                d.content(content);
+                d.components(|c| c);
+                d.allowed_mentions(|a| a.empty_parse());
+                d
+            });
+            r
+        })
+        .await?;
+
+    Ok(())
+}
+
+#[subcommand]
+async fn export_settings(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let data = ctx.ctx.data.read().await;
+    let cache = data.get::<GuildSettingsStore>().unwrap().read().await;
+    let guild_id = ctx.guild_id().unwrap();
+    let export = serde_json::to_vec_pretty(&cache.get_or_default(guild_id))?;
+
+    drop(cache);
+
+    ctx.send_message(|m| {
+        m.content(t!(locale, "settings.export"));
+        m.add_file(AttachmentType::Bytes {
+            data: Cow::from(export),
+            filename: format!("settings_{}.json", guild_id),
+        });
+        m.allowed_mentions(|a| a.empty_parse());
+        m
+    })
+    .await?;
+
+    Ok(())
+}
+
+// Arguments: Optional String message
+#[subcommand(SETTINGS_MANAGER)]
+async fn import_settings(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+    let channel_id = ctx.channel().await?.id();
+    let author_id = ctx.author().unwrap().id;
+
+    // Slash commands can't take files so we look for the file in the channel instead
+    let attachment = match ctx.get_str_arg("message") {
+        Some(message_id) => match message_id.parse::<u64>() {
+            Ok(message_id) => channel_id
+                .message(&ctx.ctx, message_id)
+                .await?
+                .attachments
+                .into_iter()
+                .next(),
+            Err(_) => None,
+        },
+        None => channel_id
+            .messages(&ctx.ctx, |b| b.limit(50))
+            .await?
+            .into_iter()
+            .filter(|m| m.author.id == author_id)
+            .find_map(|m| m.attachments.into_iter().find(|a| a.filename.ends_with(".json"))),
+    };
+
+    let attachment = match attachment {
+        Some(a) => a,
+        None => {
+            ctx.send_str(&t!(locale, "settings.import.no_file")).await?;
+            return Ok(());
+        }
+    };
+
+    if attachment.size > MAX_IMPORT_SIZE {
+        let max = MAX_IMPORT_SIZE / 1024;
+        ctx.send_str(&t!(locale, "settings.import.too_big", max = max)).await?;
+        return Ok(());
+    }
+
+    // Discord adds a charset to the content type, `application/json; charset=utf-8`
+    let is_json = attachment.filename.ends_with(".json")
+        && attachment
+            .content_type
+            .as_deref()
+            .map_or(true, |t| t.starts_with("application/json"));
+    if !is_json {
+        ctx.send_str(&t!(locale, "settings.import.not_json")).await?;
+        return Ok(());
+    }
+
+    let file = attachment.download().await?;
+
+    let mut imported = match serde_json::from_slice::<GuildSettings>(&file) {
+        Ok(s) => s,
+        Err(e) => {
+            let error = t!(locale, "settings.import.invalid", error = e);
+            send_without_mentions(ctx, &error).await?;
+            return Ok(());
+        }
+    };
+
+    if let Err(e) = imported.validate() {
+        send_without_mentions(ctx, &e.localized(locale)).await?;
+        return Ok(());
+    }
+
+    let guild_id = ctx.guild_id().unwrap();
+    let channels = guild_id.channels(&ctx.ctx).await?;
+
+    if let Some(c) = imported
+        .channel_filters
+        .iter()
+        .chain(imported.channel_overrides.keys())
+        .chain(imported.log_channel.iter())
+        .chain(imported.announce_channel.iter())
+        .find(|c| !channels.contains_key(*c))
+    {
+        ctx.send_str(&t!(locale, "settings.import.unknown_channel", channel = c))
+            .await?;
+        return Ok(());
+    }
+
+    let roles = ctx.guild().await?.roles;
+
+    if let Some(r) = imported
+        .manager_roles
+        .iter()
+        .find(|r| !roles.contains_key(*r))
+    {
+        ctx.send_str(&t!(locale, "settings.import.unknown_role", role = r))
+            .await?;
+        return Ok(());
+    }
+
+    // Only administrators can choose who manages the bot
+    let is_admin = ctx
+        .member()
+        .await?
+        .permissions(&ctx.ctx)
+        .await?
+        .administrator();
+
+    let data = ctx.ctx.data.write().await;
+    let mut settings = data.get::<GuildSettingsStore>().unwrap().write().await;
+
+    if !is_admin {
+        imported.manager_roles = settings.manager_roles(guild_id);
+    }
+
+    *settings.get_mut_or_default(guild_id) = imported;
+
+    ctx.send_str(&t!(locale, "settings.import.done")).await?;
+
+    let change = SettingChange::new(
+        "all settings",
+        "-",
+        format!("imported from {}", attachment.filename),
+    );
+    let entry = audit(ctx, &data, &settings, change).await;
+    drop(settings);
+    drop(data);
+    entry.post(&ctx.ctx).await;
 
     Ok(())
 }
@@ -1,4 +1,8 @@
-use serenity::{builder::CreateEmbed, model::id::UserId, utils::Colour};
+use serenity::{
+    builder::CreateEmbed,
+    model::id::UserId,
+    utils::Colour,
+};
 use slashy::{
     argument::Argument,
     command,
@@ -8,7 +12,15 @@ use slashy::{
 };
 
 use crate::{
-    database::{get_or_create_user, tables::leaderboards::get_or_create_server_user},
+    bot::{locale::command_locale, members::MemberNames},
+    database::{
+        get_or_create_user,
+        tables::{
+            history::get_chain_stats,
+            leaderboards::{get_global_rank, get_or_create_server_user, get_server_rank},
+        },
+    },
+    t,
     DatabaseConn,
 };
 
@@ -24,6 +36,7 @@ command! {
 
 #[subcommand]
 async fn stats(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
     let data = ctx.ctx.data.read().await;
     let database = data.get::<DatabaseConn>().unwrap().lock().await;
 
@@ -37,18 +50,27 @@ async fn stats(ctx: &CommandContext) -> CommandResult {
     let self_user = ctx.ctx.http.get_current_user().await?;
     let guild = ctx.guild().await?;
     let server_user = get_or_create_server_user(&database, guild.id, UserId(user.id.into()));
-    let caller = ctx.member().await?;
+    let server_rank = get_server_rank(&database, guild.id, server_user.points);
+    let global_rank = get_global_rank(&database, user.points);
+    let chain_stats = get_chain_stats(&database, guild.id, target);
     let member = guild.member(&ctx.ctx, self_user.id).await?;
     let color = member.colour(&ctx.ctx).await.unwrap_or(Colour::MAGENTA);
 
     drop(database);
 
+    let name = MemberNames::new(&ctx.ctx, Some(guild.id), locale).get(target).await;
+
     ctx.send_embed(|e: &mut CreateEmbed| {
-        e.title(format!("{}'s Stats", caller.display_name()))
-            .field("Server Stats", "———————————————", false)
-            .field("Points", format!("{} points", server_user.points), true)
+        e.title(t!(locale, "stats.title", name = name))
+            .field(t!(locale, "stats.server_stats"), "———————————————", false)
             .field(
-                "Longest Chains",
+                t!(locale, "stats.points"),
+                t!(locale, "stats.points_value", points = server_user.points),
+                true,
+            )
+            .field(t!(locale, "stats.rank"), format!("#{}", server_rank), true)
+            .field(
+                t!(locale, "stats.longest_chains"),
                 server_user
                     .longest_chains
                     .iter()
@@ -67,10 +89,30 @@ async fn stats(ctx: &CommandContext) -> CommandResult {
                     }),
                 true,
             )
-            .field("Global Stats", "———————————————", false)
-            .field("Points", format!("{} points", user.points), true)
+            .field(t!(locale, "stats.chains_joined"), chain_stats.joined, true)
+            .field(t!(locale, "stats.chains_started"), chain_stats.started, true)
+            .field(t!(locale, "stats.chains_broken"), chain_stats.broken, true)
+            .field(
+                t!(locale, "stats.average_length"),
+                format!("{:.1}", chain_stats.average_length()),
+                true,
+            )
+            .field(
+                t!(locale, "stats.favourite_channel"),
+                chain_stats
+                    .favourite_channel
+                    .map_or_else(|| t!(locale, "stats.none"), |c| format!("<#{}>", c.0)),
+                true,
+            )
+            .field(t!(locale, "stats.global_stats"), "———————————————", false)
+            .field(
+                t!(locale, "stats.points"),
+                t!(locale, "stats.points_value", points = user.points),
+                true,
+            )
+            .field(t!(locale, "stats.rank"), format!("#{}", global_rank), true)
             .field(
-                "Longest Chains",
+                t!(locale, "stats.longest_chains"),
                 user.longest_chains
                     .iter()
                     .enumerate()
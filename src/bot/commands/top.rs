@@ -1,4 +1,7 @@
-use serenity::futures::future::join_all;
+use serenity::{
+    client::Context,
+    model::id::{GuildId, UserId},
+};
 use slashy::{
     argument::Argument,
     command,
@@ -8,10 +11,12 @@ use slashy::{
 };
 
 use crate::{
+    bot::{locale::command_locale, members::MemberNames, mentions::send_without_mentions},
     database::tables::leaderboards::{
         get_global_leaderboard_by_points,
         get_server_leaderboard_by_points,
     },
+    t,
     DatabaseConn,
 };
 
@@ -27,6 +32,8 @@ command! {
 
 #[subcommand]
 async fn top(ctx: &CommandContext) -> CommandResult {
+    let locale = command_locale(ctx).await;
+
     // Get arguments
     let start_index = match ctx.get_arg("page") {
         Some(Argument::Integer(i)) => i * 10,
@@ -41,79 +48,80 @@ async fn top(ctx: &CommandContext) -> CommandResult {
     let rankings = match ctx.guild_id() {
         Some(g) => {
             let leaderboard = get_server_leaderboard_by_points(&database, g);
-
-            let leaderboard_slice = if start_index >= 0
-                && (start_index as usize) < leaderboard.len() - 1
-                && start_index as usize + 10 < leaderboard.len() - 1
-            {
-                &leaderboard[start_index as usize .. start_index as usize + 10]
-            } else if start_index >= 0 && (start_index as usize) < leaderboard.len() - 1 {
-                &leaderboard[start_index as usize ..]
-            } else {
-                &[]
-            };
-
-
-            let mut futures = Vec::new();
-
-            for (placement, user) in leaderboard_slice.iter().enumerate() {
-                futures.push(async move {
-                    format!(
-                        "{}: {} ({} points)",
-                        placement as i32 + start_index + 1,
-                        g.member(&ctx.ctx, user.user_id.0)
-                            .await
-                            .unwrap()
-                            .display_name(),
-                        user.points
-                    )
-                });
+            if leaderboard.is_empty() {
+                ctx.send_str(&t!(locale, "top.empty")).await?;
+                return Ok(());
             }
 
+            let entries = page(&leaderboard, start_index)
+                .iter()
+                .map(|u| (UserId(u.user_id.0), u.points))
+                .collect::<Vec<_>>();
 
-            join_all(futures).await.join("\n")
+            ranking_lines(&ctx.ctx, Some(g), locale, &entries, start_index).await
         }
         None => {
             let leaderboard = get_global_leaderboard_by_points(&database);
-
-            let leaderboard_slice = if start_index > 0
-                && (start_index as usize) < leaderboard.len() - 1
-                && start_index as usize + 10 < leaderboard.len() - 1
-            {
-                &leaderboard[start_index as usize .. start_index as usize + 10]
-            } else if start_index > 0 && (start_index as usize) < leaderboard.len() - 1 {
-                &leaderboard[start_index as usize ..]
-            } else {
-                &[]
-            };
-
-            let mut futures = Vec::new();
-
-            for (placement, user) in leaderboard_slice.iter().enumerate() {
-                futures.push(async move {
-                    format!(
-                        "{}: {} ({} points)",
-                        placement as i32 + start_index + 1,
-                        ctx.ctx.http.get_user(user.id.into()).await.unwrap().name,
-                        user.points
-                    )
-                })
+            if leaderboard.is_empty() {
+                ctx.send_str(&t!(locale, "top.empty")).await?;
+                return Ok(());
             }
 
-            join_all(futures).await.join("\n")
+            let entries = page(&leaderboard, start_index)
+                .iter()
+                .map(|u| (UserId(u.id.0), u.points))
+                .collect::<Vec<_>>();
+
+            ranking_lines(&ctx.ctx, None, locale, &entries, start_index).await
         }
     };
 
     let header = match ctx.guild().await {
         Ok(g) => {
-            format!("-- Showing {} server leaderboard --", g.name)
+            t!(locale, "top.server_header", server = g.name)
         }
-        Err(_) => "Showing global leaderboard".to_owned(),
+        Err(_) => t!(locale, "top.global_header"),
     };
 
-    ctx.send_str(&format!("```r\n{}\n\n{}```", header, rankings))
-        .await?;
-
+    send_without_mentions(ctx, &format!("```r\n{}\n\n{}```", header, rankings)).await?;
 
     Ok(())
 }
+
+/// The 10 entries of the leaderboard from `start_index`, pages past the end are empty
+fn page<T>(leaderboard: &[T], start_index: i32) -> &[T] {
+    if start_index < 0 {
+        return &[];
+    }
+
+    let start = (start_index as usize).min(leaderboard.len());
+    let end = (start + 10).min(leaderboard.len());
+    &leaderboard[start .. end]
+}
+
+/// A line for each user on a page of the leaderboard, looking up all of their names at once
+async fn ranking_lines(
+    ctx: &Context,
+    guild_id: Option<GuildId>,
+    locale: &'static str,
+    entries: &[(UserId, i64)],
+    start_index: i32,
+) -> String {
+    let mut names = MemberNames::new(ctx, guild_id, locale);
+    names.resolve(entries.iter().map(|(u, _)| *u)).await;
+
+    entries
+        .iter()
+        .enumerate()
+        .map(|(placement, (user, points))| {
+            t!(
+                locale,
+                "top.entry",
+                placement = placement as i32 + start_index + 1,
+                name = names.name(*user),
+                points = points
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
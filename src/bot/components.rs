@@ -0,0 +1,62 @@
+use serenity::{
+    async_trait,
+    client::{Context, EventHandler},
+    model::interactions::{
+        message_component::MessageComponentInteraction,
+        Interaction,
+        InteractionApplicationCommandCallbackDataFlags,
+        InteractionResponseType,
+    },
+};
+use slashy::commands::CommandResult;
+
+use crate::bot::commands::{privacy_component, settings_component};
+
+/// Routes message component interactions back to the command that sent the component
+///
+/// Component custom ids are formatted as `command:arg:arg...`
+pub struct ComponentHandler;
+
+#[async_trait]
+impl EventHandler for ComponentHandler {
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        if let Interaction::MessageComponent(component) = interaction {
+            let custom_id = component.data.custom_id.clone();
+            let mut args = custom_id.split(':');
+            let command = args.next().unwrap_or_default();
+            let args = args.collect::<Vec<_>>();
+
+            let result = match command {
+                "privacy" => privacy_component(&ctx, &component, &args).await,
+                "settings" => settings_component(&ctx, &component, &args).await,
+                _ => Ok(()),
+            };
+
+            if let Err(e) = result {
+                println!("Error handling component {}: {:?}", custom_id, e);
+            }
+        }
+    }
+}
+
+/// Responds to a component with a message only the user who clicked it can see
+pub async fn respond_ephemeral(
+    ctx: &Context,
+    interaction: &MessageComponentInteraction,
+    content: &str,
+) -> CommandResult {
+    interaction
+        .create_interaction_response(ctx, |r| {
+            r.kind(InteractionResponseType::ChannelMessageWithSource);
+            r.interaction_response_data(|d| {
+                d.content(content);
+                d.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL);
+                d.allowed_mentions(|a| a.empty_parse());
+                d
+            });
+            r
+        })
+        .await?;
+
+    Ok(())
+}
@@ -1,18 +1,31 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
     sync::{Arc, Mutex},
+    time::Duration,
 };
 
-use diesel::PgConnection;
+use diesel::{sql_types::Text, PgConnection};
 use serenity::{
-    model::id::{ChannelId, GuildId},
+    model::{
+        channel::ReactionType,
+        id::{ChannelId, GuildId, RoleId},
+    },
     prelude::{RwLock, TypeMapKey},
 };
 
 use lazy_static::lazy_static;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use slashy::settings::SettingsProvider;
 
-use crate::database::{establish_connection, guilds::*};
+use crate::{
+    bot::locale::{DEFAULT_LOCALE, LOCALES},
+    chain::{styles::STYLES, template::validate_template},
+    database::{establish_connection, guilds::*},
+    t,
+};
 
 pub struct GuildSettingsStore;
 
@@ -28,6 +41,8 @@ pub struct GuildSettingsCache {
     // This mutex is mainly used to allow PgConnection to be sent over threads
     database_connection: Arc<Mutex<PgConnection>>,
     testing_guilds: Vec<GuildId>,
+    // Guilds changed since the last flush, only these get written back to the database
+    dirty: HashSet<GuildId>,
 }
 
 macro_rules! get_field {
@@ -47,10 +62,22 @@ impl GuildSettingsCache {
         prefixes, prefixes_mut, Vec<String>,
         channel_filters, channel_filters_mut, Vec<ChannelId>,
         blacklist, blacklist_mut, bool,
-        style, style_mut, String,
+        style, style_mut, StyleName,
         remove_messages, remove_messages_mut, bool,
         chain_threshold, chain_threshold_mut, u16,
-        alternate_member, alternate_member_mut, bool
+        alternate_member, alternate_member_mut, bool,
+        match_mode, match_mode_mut, MatchMode,
+        channel_overrides, channel_overrides_mut, HashMap<ChannelId, ChannelSettings>,
+        manager_roles, manager_roles_mut, Vec<RoleId>,
+        log_channel, log_channel_mut, Option<ChannelId>,
+        response_template, response_template_mut, Option<String>,
+        react_emoji, react_emoji_mut, Option<String>,
+        log_responses, log_responses_mut, bool,
+        announce_channel, announce_channel_mut, Option<ChannelId>,
+        milestones, milestones_mut, Vec<u16>,
+        locale, locale_mut, Option<String>,
+        ping_participants, ping_participants_mut, bool,
+        replay_chains, replay_chains_mut, bool
     }
 
     pub fn new(testing_guilds: Vec<GuildId>) -> Self {
@@ -58,20 +85,34 @@ impl GuildSettingsCache {
             database_connection: Arc::new(Mutex::new(establish_connection())),
             guild_map: HashMap::new(),
             testing_guilds,
+            dirty: HashSet::new(),
         }
     }
 
-    pub fn save(&self) {
+    /// Writes every guild changed since the last flush to the database
+    ///
+    /// Returns the number of guilds written
+    pub fn flush(&mut self) -> usize {
         let conn = self.database_connection.lock().unwrap();
-        for (id, settings) in &self.guild_map {
-            update_guild(&conn, *id, settings)
+        let dirty = self.dirty.drain().collect::<Vec<_>>();
+
+        for guild_id in &dirty {
+            if let Some(settings) = self.guild_map.get(guild_id) {
+                update_guild(&conn, *guild_id, settings)
+            }
         }
+
+        dirty.len()
     }
 
-    pub fn save_guild(&self, guild_id: GuildId) {
-        let conn = self.database_connection.lock().unwrap();
-        let guild = self.get_or_default(guild_id);
-        update_guild(&conn, guild_id, &guild)
+    /// Writes one guild to the database now, for changes that shouldn't wait for the next flush
+    pub fn flush_guild(&mut self, guild_id: GuildId) {
+        if self.dirty.remove(&guild_id) {
+            if let Some(settings) = self.guild_map.get(&guild_id) {
+                let conn = self.database_connection.lock().unwrap();
+                update_guild(&conn, guild_id, settings);
+            }
+        }
     }
 
     pub fn load_guilds(&mut self) {
@@ -79,11 +120,55 @@ impl GuildSettingsCache {
         self.guild_map = get_guilds(&conn);
     }
 
+    /// Makes sure a guild we are in has settings and is marked as active
+    pub fn join_guild(&mut self, guild_id: GuildId) {
+        let conn = self.database_connection.lock().unwrap();
+
+        // Not using get_mut_or_default as that would mark every guild dirty on startup
+        if !self.guild_map.contains_key(&guild_id) {
+            // Guilds we left are still stored until their retention period runs out
+            let settings = get_guild(&conn, guild_id).unwrap_or_else(|| new_guild(&conn, guild_id));
+            self.guild_map.insert(guild_id, settings);
+        }
+
+        mark_guild_active(&conn, guild_id);
+    }
+
+    /// Marks a guild as left, its data is kept in the database until the retention period runs out
+    pub fn leave_guild(&mut self, guild_id: GuildId) {
+        // Save anything waiting for a flush, then stop holding the guild in memory
+        self.flush_guild(guild_id);
+        self.guild_map.remove(&guild_id);
+
+        let conn = self.database_connection.lock().unwrap();
+        mark_guild_inactive(&conn, guild_id);
+    }
+
+    /// Deletes the data of every guild we left more than `retention` ago
+    ///
+    /// Returns the number of guilds purged
+    pub fn purge_departed_guilds(&mut self, retention: Duration) -> usize {
+        let conn = self.database_connection.lock().unwrap();
+        let removed = purge_inactive_guilds(&conn, retention);
+
+        for guild_id in &removed {
+            self.guild_map.remove(guild_id);
+            self.dirty.remove(guild_id);
+        }
+
+        removed.len()
+    }
+
     pub fn get(&self, guild_id: GuildId) -> Option<&GuildSettings> {
         self.guild_map.get(&guild_id)
     }
 
+    /// Mutable access marks the guild as dirty so it gets saved on the next flush
     pub fn get_mut<'a>(&'a mut self, guild_id: GuildId) -> Option<&mut GuildSettings> {
+        if self.guild_map.contains_key(&guild_id) {
+            self.dirty.insert(guild_id);
+        }
+
         self.guild_map.get_mut(&guild_id)
     }
 
@@ -91,11 +176,13 @@ impl GuildSettingsCache {
         if self.guild_map.contains_key(&guild_id) {
             self.guild_map.get(&guild_id).unwrap().clone()
         } else {
-            DM_SETTINGS.to_owned()
+            DEFAULT_SETTINGS.to_owned()
         }
     }
 
     pub fn get_mut_or_default(&mut self, guild_id: GuildId) -> &mut GuildSettings {
+        self.dirty.insert(guild_id);
+
         if self.guild_map.contains_key(&guild_id) {
             self.guild_map.get_mut(&guild_id).unwrap()
         } else {
@@ -108,15 +195,15 @@ impl GuildSettingsCache {
 }
 
 impl Drop for GuildSettingsCache {
-    // Save guild settings before we drop to make sure nothing has gone wrong
+    // Save any unflushed changes before we drop to make sure nothing is lost
     fn drop(&mut self) {
-        self.save();
+        self.flush();
     }
 }
 
 impl SettingsProvider for GuildSettingsCache {
     fn default_prefixes(&self) -> Vec<String> {
-        DM_SETTINGS.prefixes.clone()
+        DEFAULT_SETTINGS.prefixes.clone()
     }
 
     fn prefixes(&self, guild_id: GuildId) -> Option<Vec<String>> {
@@ -136,25 +223,590 @@ impl SettingsProvider for GuildSettingsCache {
     }
 }
 
-#[derive(Clone)]
+/// The smallest chain threshold we allow
+pub const MIN_CHAIN_THRESHOLD: u16 = 1;
+/// The largest chain threshold we allow
+pub const MAX_CHAIN_THRESHOLD: u16 = 1000;
+/// Chains start at 2 messages, so that's the smallest length worth announcing
+pub const MIN_MILESTONE: u16 = 2;
+pub const MAX_MILESTONE: u16 = 10000;
+/// The most milestones a guild can have
+pub const MAX_MILESTONES: usize = 20;
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct GuildSettings {
     pub prefixes: Vec<String>,
     pub channel_filters: Vec<ChannelId>,
     pub blacklist: bool,
-    pub style: String,
+    pub style: StyleName,
     pub remove_messages: bool,
     pub chain_threshold: u16,
     pub alternate_member: bool,
+    #[serde(default)]
+    pub match_mode: MatchMode,
+    #[serde(default)]
+    pub channel_overrides: HashMap<ChannelId, ChannelSettings>,
+    #[serde(default)]
+    pub manager_roles: Vec<RoleId>,
+    #[serde(default)]
+    pub log_channel: Option<ChannelId>,
+    #[serde(default)]
+    pub response_template: Option<String>,
+    /// The emoji the react style uses, when unset the chain length is spelled out in emoji
+    #[serde(default)]
+    pub react_emoji: Option<String>,
+    /// Whether chain embeds are sent to the log channel instead of the chain's channel
+    #[serde(default)]
+    pub log_responses: bool,
+    /// Where chain results are posted instead of the chain's channel
+    #[serde(default)]
+    pub announce_channel: Option<ChannelId>,
+    /// Chain lengths that get announced while the chain is still running
+    #[serde(default = "default_milestones")]
+    pub milestones: Vec<u16>,
+    /// The language responses are sent in, when unset Discord's locales are used
+    #[serde(default)]
+    pub locale: Option<String>,
+    /// Whether chain responses ping everyone in the chain
+    #[serde(default)]
+    pub ping_participants: bool,
+    /// Whether chains with removed messages are replayed as a single webhook message
+    #[serde(default)]
+    pub replay_chains: bool,
+}
+
+fn default_milestones() -> Vec<u16> {
+    vec![10, 25, 50, 100]
+}
+
+impl GuildSettings {
+    /// Checks that the settings have sensible values
+    ///
+    /// Channel ids are not checked as that needs to know what guild the settings are for
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        validate_prefixes(&self.prefixes)?;
+        parse_threshold(self.chain_threshold as i64)?;
+
+        if let Some(template) = &self.response_template {
+            validate_template(template).map_err(SettingsError::InvalidTemplate)?;
+        }
+
+        if let Some(emoji) = &self.react_emoji {
+            validate_react_emoji(emoji)?;
+        }
+
+        validate_milestones(&self.milestones)?;
+
+        if let Some(locale) = &self.locale {
+            validate_locale(locale)?;
+        }
+
+        for overrides in self.channel_overrides.values() {
+            if let Some(threshold) = overrides.chain_threshold {
+                parse_threshold(threshold as i64)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Resets a setting to its default, `all` resets every setting
+    pub fn reset(&mut self, setting: &str) -> Result<(), SettingsError> {
+        let defaults = DEFAULT_SETTINGS.clone();
+
+        match setting {
+            "all" => *self = defaults,
+            "prefixes" => self.prefixes = defaults.prefixes,
+            "channel_filters" => self.channel_filters = defaults.channel_filters,
+            "blacklist" => self.blacklist = defaults.blacklist,
+            "style" => self.style = defaults.style,
+            "remove_messages" => self.remove_messages = defaults.remove_messages,
+            "chain_threshold" => self.chain_threshold = defaults.chain_threshold,
+            "alternate_member" => self.alternate_member = defaults.alternate_member,
+            "match_mode" => self.match_mode = defaults.match_mode,
+            "channel_overrides" => self.channel_overrides = defaults.channel_overrides,
+            "manager_roles" => self.manager_roles = defaults.manager_roles,
+            "log_channel" => self.log_channel = defaults.log_channel,
+            "response_template" => self.response_template = defaults.response_template,
+            "react_emoji" => self.react_emoji = defaults.react_emoji,
+            "log_responses" => self.log_responses = defaults.log_responses,
+            "announce_channel" => self.announce_channel = defaults.announce_channel,
+            "milestones" => self.milestones = defaults.milestones,
+            "locale" => self.locale = defaults.locale,
+            "ping_participants" => self.ping_participants = defaults.ping_participants,
+            "replay_chains" => self.replay_chains = defaults.replay_chains,
+            _ => return Err(SettingsError::UnknownSetting(setting.to_owned())),
+        }
+
+        Ok(())
+    }
+
+    /// Gets the settings for a channel, with any of the channel's overrides applied
+    pub fn for_channel(&self, channel_id: ChannelId) -> GuildSettings {
+        let mut settings = self.clone();
+
+        if let Some(overrides) = self.channel_overrides.get(&channel_id) {
+            settings.chain_threshold = overrides.chain_threshold.unwrap_or(self.chain_threshold);
+            settings.style = overrides.style.unwrap_or(self.style);
+            settings.remove_messages = overrides.remove_messages.unwrap_or(self.remove_messages);
+            settings.alternate_member = overrides.alternate_member.unwrap_or(self.alternate_member);
+            settings.match_mode = overrides.match_mode.unwrap_or(self.match_mode);
+        }
+
+        settings
+    }
+}
+
+/// The names of the settings that can be overridden per channel
+pub const CHANNEL_SETTINGS: [&str; 5] = [
+    "chain_threshold",
+    "style",
+    "remove_messages",
+    "alternate_member",
+    "match_mode",
+];
+
+/// Settings for a single channel that override the guild's settings
+///
+/// `None` means the channel uses the guild's setting
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct ChannelSettings {
+    pub chain_threshold: Option<u16>,
+    pub style: Option<StyleName>,
+    pub remove_messages: Option<bool>,
+    pub alternate_member: Option<bool>,
+    pub match_mode: Option<MatchMode>,
+}
+
+impl ChannelSettings {
+    /// Parses and sets the override for a setting
+    pub fn set(&mut self, setting: &str, value: &str) -> Result<(), SettingsError> {
+        match setting {
+            "chain_threshold" =>
+                self.chain_threshold = Some(parse_threshold(
+                    value
+                        .parse()
+                        .map_err(|_| SettingsError::InvalidNumber(value.to_owned()))?,
+                )?),
+            "style" => self.style = Some(value.parse()?),
+            "remove_messages" => self.remove_messages = Some(parse_bool(value)?),
+            "alternate_member" => self.alternate_member = Some(parse_bool(value)?),
+            "match_mode" => self.match_mode = Some(value.parse()?),
+            _ => return Err(SettingsError::UnknownSetting(setting.to_owned())),
+        }
+
+        Ok(())
+    }
+
+    /// Gets the override for a setting as text, if the channel overrides it
+    pub fn get(&self, setting: &str) -> Option<String> {
+        match setting {
+            "chain_threshold" => self.chain_threshold.map(|t| t.to_string()),
+            "style" => self.style.map(|s| s.to_string()),
+            "remove_messages" => self.remove_messages.map(|r| r.to_string()),
+            "alternate_member" => self.alternate_member.map(|a| a.to_string()),
+            "match_mode" => self.match_mode.map(|m| m.to_string()),
+            _ => None,
+        }
+    }
+
+    /// Removes the override for a setting
+    pub fn clear(&mut self, setting: &str) -> Result<(), SettingsError> {
+        match setting {
+            "chain_threshold" => self.chain_threshold = None,
+            "style" => self.style = None,
+            "remove_messages" => self.remove_messages = None,
+            "alternate_member" => self.alternate_member = None,
+            "match_mode" => self.match_mode = None,
+            _ => return Err(SettingsError::UnknownSetting(setting.to_owned())),
+        }
+
+        Ok(())
+    }
+
+    /// Whether the channel doesn't override anything
+    pub fn is_empty(&self) -> bool {
+        self.chain_threshold.is_none()
+            && self.style.is_none()
+            && self.remove_messages.is_none()
+            && self.alternate_member.is_none()
+            && self.match_mode.is_none()
+    }
+}
+
+/// How messages are compared to decide if they continue a chain
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, AsExpression, FromSqlRow)]
+#[serde(rename_all = "snake_case")]
+#[sql_type = "Text"]
+pub enum MatchMode {
+    Exact,
+    IgnoreCase,
+}
+
+impl MatchMode {
+    pub const ALL: [MatchMode; 2] = [MatchMode::Exact, MatchMode::IgnoreCase];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MatchMode::Exact => "exact",
+            MatchMode::IgnoreCase => "ignore_case",
+        }
+    }
+
+    /// Whether `message` continues a chain of `chain`
+    pub fn matches(&self, chain: &str, message: &str) -> bool {
+        match self {
+            MatchMode::Exact => chain == message,
+            MatchMode::IgnoreCase => chain.to_lowercase() == message.to_lowercase(),
+        }
+    }
+}
+
+impl Default for MatchMode {
+    fn default() -> Self {
+        MatchMode::Exact
+    }
+}
+
+impl FromStr for MatchMode {
+    type Err = SettingsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        MatchMode::ALL
+            .iter()
+            .find(|mode| mode.name() == s)
+            .copied()
+            .ok_or_else(|| SettingsError::UnknownMatchMode(s.to_owned()))
+    }
+}
+
+impl Display for MatchMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// The name of a style in the style registry, used to pick how chain responses are sent
+#[derive(Clone, Copy, Debug, PartialEq, Eq, AsExpression, FromSqlRow)]
+#[sql_type = "Text"]
+pub struct StyleName(&'static str);
+
+impl StyleName {
+    pub fn name(&self) -> &'static str {
+        self.0
+    }
+}
+
+impl FromStr for StyleName {
+    type Err = SettingsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        STYLES
+            .get(s)
+            .map(|style| StyleName(style.name()))
+            .ok_or_else(|| SettingsError::UnknownStyle(s.to_owned()))
+    }
+}
+
+impl Display for StyleName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl Serialize for StyleName {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for StyleName {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Why a setting was rejected
+#[derive(Debug)]
+pub enum SettingsError {
+    UnknownStyle(String),
+    UnknownMatchMode(String),
+    UnknownSetting(String),
+    ThresholdOutOfRange(i64),
+    InvalidNumber(String),
+    InvalidBool(String),
+    NoPrefixes,
+    EmptyPrefix,
+    InvalidTemplate(String),
+    InvalidEmoji(String),
+    MilestoneOutOfRange(i64),
+    TooManyMilestones,
+    UnknownLocale(String),
+}
+
+impl SettingsError {
+    /// Explains the error in a locale
+    pub fn localized(&self, locale: &str) -> String {
+        match self {
+            SettingsError::UnknownStyle(style) => t!(
+                locale,
+                "error.unknown_style",
+                style = style,
+                styles = STYLES.names().collect::<Vec<_>>().join(", ")
+            ),
+            SettingsError::UnknownMatchMode(mode) => t!(
+                locale,
+                "error.unknown_match_mode",
+                mode = mode,
+                modes = MatchMode::ALL
+                    .iter()
+                    .map(|m| m.name())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            SettingsError::UnknownSetting(setting) =>
+                t!(locale, "error.unknown_setting", setting = setting),
+            SettingsError::ThresholdOutOfRange(threshold) => t!(
+                locale,
+                "error.threshold_out_of_range",
+                min = MIN_CHAIN_THRESHOLD,
+                max = MAX_CHAIN_THRESHOLD,
+                threshold = threshold
+            ),
+            SettingsError::InvalidNumber(value) =>
+                t!(locale, "error.invalid_number", value = value),
+            SettingsError::InvalidBool(value) => t!(locale, "error.invalid_bool", value = value),
+            SettingsError::NoPrefixes => t!(locale, "error.no_prefixes"),
+            SettingsError::EmptyPrefix => t!(locale, "error.empty_prefix"),
+            SettingsError::InvalidTemplate(reason) =>
+                t!(locale, "error.invalid_template", reason = reason),
+            SettingsError::InvalidEmoji(emoji) => t!(locale, "error.invalid_emoji", emoji = emoji),
+            SettingsError::MilestoneOutOfRange(milestone) => t!(
+                locale,
+                "error.milestone_out_of_range",
+                min = MIN_MILESTONE,
+                max = MAX_MILESTONE,
+                milestone = milestone
+            ),
+            SettingsError::TooManyMilestones =>
+                t!(locale, "error.too_many_milestones", max = MAX_MILESTONES),
+            SettingsError::UnknownLocale(locale_code) => t!(
+                locale,
+                "error.unknown_locale",
+                locale = locale_code,
+                locales = LOCALES.iter().map(|l| l.code).collect::<Vec<_>>().join(", ")
+            ),
+        }
+    }
+}
+
+impl Display for SettingsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.localized(DEFAULT_LOCALE))
+    }
+}
+
+impl Error for SettingsError {}
+
+/// Checks a chain threshold is in range, converting it to the type we store it as
+pub fn parse_threshold(threshold: i64) -> Result<u16, SettingsError> {
+    if threshold < MIN_CHAIN_THRESHOLD as i64 || threshold > MAX_CHAIN_THRESHOLD as i64 {
+        Err(SettingsError::ThresholdOutOfRange(threshold))
+    } else {
+        Ok(threshold as u16)
+    }
+}
+
+/// Checks a milestone is in range, converting it to the type we store it as
+pub fn parse_milestone(milestone: i64) -> Result<u16, SettingsError> {
+    if milestone < MIN_MILESTONE as i64 || milestone > MAX_MILESTONE as i64 {
+        Err(SettingsError::MilestoneOutOfRange(milestone))
+    } else {
+        Ok(milestone as u16)
+    }
+}
+
+/// Checks every milestone is in range and there aren't too many of them
+pub fn validate_milestones(milestones: &[u16]) -> Result<(), SettingsError> {
+    if milestones.len() > MAX_MILESTONES {
+        return Err(SettingsError::TooManyMilestones);
+    }
+
+    for milestone in milestones {
+        parse_milestone(*milestone as i64)?;
+    }
+
+    Ok(())
+}
+
+/// Checks an emoji looks like something we can react with
+///
+/// Discord has the final say, unknown emoji only fail when the reaction is added
+pub fn validate_react_emoji(emoji: &str) -> Result<(), SettingsError> {
+    let invalid = || SettingsError::InvalidEmoji(emoji.to_owned());
+
+    match emoji.parse::<ReactionType>().map_err(|_| invalid())? {
+        ReactionType::Unicode(e) if !is_single_emoji(&e) => Err(invalid()),
+        _ if emoji.chars().any(char::is_whitespace) => Err(invalid()),
+        _ => Ok(()),
+    }
+}
+
+/// Whether a string is one emoji, counting keycaps, flags and joined sequences as one
+fn is_single_emoji(emoji: &str) -> bool {
+    let chars = emoji.chars().collect::<Vec<_>>();
+
+    // Keycaps are a digit, # or * followed by the keycap mark
+    if let [base, rest @ ..] = chars.as_slice() {
+        if matches!(base, '0'..='9' | '#' | '*') {
+            return matches!(rest, ['\u{20e3}'] | ['\u{fe0f}', '\u{20e3}']);
+        }
+    }
+
+    let mut emoji_count = 0;
+    let mut joined = false;
+    let mut half_flag = false;
+    for c in chars {
+        match c as u32 {
+            // Zero width joiner, the next emoji is part of this one
+            0x200D => joined = true,
+            // Variation selectors, tags and skin tones only change the emoji before them
+            0xFE0E | 0xFE0F | 0xE0020..=0xE007F | 0x1F3FB..=0x1F3FF => {}
+            // Flags are a pair of regional indicators
+            0x1F1E6..=0x1F1FF => {
+                if !half_flag {
+                    emoji_count += 1;
+                }
+                half_flag = !half_flag;
+            }
+            _ if is_emoji_char(c) => {
+                if !joined {
+                    emoji_count += 1;
+                }
+                joined = false;
+            }
+            _ => return false,
+        }
+    }
+
+    emoji_count == 1 && !joined && !half_flag
+}
+
+/// Whether a character is in one of the blocks emoji come from
+fn is_emoji_char(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1F000..=0x1FAFF
+            | 0x2600..=0x27BF
+            | 0x2300..=0x23FF
+            | 0x2B00..=0x2BFF
+            | 0x2190..=0x21FF
+            | 0x25A0..=0x25FF
+            | 0x2934
+            | 0x2935
+            | 0x3030
+            | 0x303D
+            | 0x3297
+            | 0x3299
+            | 0xA9
+            | 0xAE
+            | 0x203C
+            | 0x2049
+            | 0x2122
+            | 0x2139
+    )
+}
+
+/// Checks a locale is one of the bundled locales
+pub fn validate_locale(locale: &str) -> Result<(), SettingsError> {
+    if LOCALES.iter().any(|l| l.code == locale) {
+        Ok(())
+    } else {
+        Err(SettingsError::UnknownLocale(locale.to_owned()))
+    }
+}
+
+/// Parses the ways someone might say yes or no
+pub fn parse_bool(value: &str) -> Result<bool, SettingsError> {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" | "on" => Ok(true),
+        "false" | "no" | "off" => Ok(false),
+        _ => Err(SettingsError::InvalidBool(value.to_owned())),
+    }
+}
+
+/// Checks there is at least one prefix and none of them are empty
+pub fn validate_prefixes(prefixes: &[String]) -> Result<(), SettingsError> {
+    if prefixes.is_empty() {
+        Err(SettingsError::NoPrefixes)
+    } else if prefixes.iter().any(|p| p.trim().is_empty()) {
+        Err(SettingsError::EmptyPrefix)
+    } else {
+        Ok(())
+    }
 }
 
 lazy_static! {
-    pub static ref DM_SETTINGS: GuildSettings = GuildSettings {
+    /// The settings used in DMs and for guilds we haven't seen before
+    ///
+    /// The bot operator can override any of them with a JSON object,
+    /// either in `DEFAULT_SETTINGS` or in a file at the path in `DEFAULT_SETTINGS_FILE`
+    pub static ref DEFAULT_SETTINGS: GuildSettings = load_default_settings();
+}
+
+fn load_default_settings() -> GuildSettings {
+    let built_in = GuildSettings {
         prefixes: vec!["cb.".to_owned()],
         channel_filters: Vec::new(),
-        blacklist: false,
-        style: "embed".to_owned(),
+        blacklist: true,
+        style: "embed".parse().unwrap(),
         remove_messages: true,
-        chain_threshold: u16::max_value(),
-        alternate_member: true
+        chain_threshold: 6,
+        alternate_member: true,
+        match_mode: MatchMode::Exact,
+        channel_overrides: HashMap::new(),
+        manager_roles: Vec::new(),
+        log_channel: None,
+        response_template: None,
+        react_emoji: None,
+        log_responses: false,
+        announce_channel: None,
+        milestones: default_milestones(),
+        locale: None,
+        ping_participants: false,
+        replay_chains: false,
+    };
+
+    let overrides = match std::env::var("DEFAULT_SETTINGS") {
+        Ok(o) => o,
+        Err(_) => match std::env::var("DEFAULT_SETTINGS_FILE") {
+            Ok(path) => std::fs::read_to_string(path).expect("Error reading DEFAULT_SETTINGS_FILE"),
+            Err(_) => return built_in,
+        },
+    };
+    let overrides = serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(&overrides)
+        .expect("Error in default settings");
+
+    let mut settings = match serde_json::to_value(built_in).unwrap() {
+        serde_json::Value::Object(s) => s,
+        _ => unreachable!(),
     };
+
+    for (setting, value) in overrides {
+        if !settings.contains_key(&setting) {
+            panic!("Unknown setting {} in default settings", setting);
+        }
+        settings.insert(setting, value);
+    }
+
+    let settings = serde_json::from_value::<GuildSettings>(serde_json::Value::Object(settings))
+        .expect("Error in default settings");
+
+    if let Err(e) = settings.validate() {
+        panic!("Invalid default settings: {}", e);
+    }
+
+    settings
 }
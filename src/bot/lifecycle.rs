@@ -0,0 +1,75 @@
+use std::{sync::Arc, time::Duration};
+
+use serenity::{
+    async_trait,
+    client::{Context, EventHandler},
+    model::guild::{Guild, GuildUnavailable},
+    prelude::RwLock,
+};
+
+use crate::bot::guild_settings::{GuildSettingsCache, GuildSettingsStore};
+
+/// How often we check for departed guilds whose data should be purged
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Keeps guild settings in sync with the guilds we are actually in
+pub struct GuildLifecycleHandler;
+
+#[async_trait]
+impl EventHandler for GuildLifecycleHandler {
+    async fn guild_create(&self, ctx: Context, guild: Guild, _is_new: bool) {
+        let data = ctx.data.read().await;
+        let mut settings = data
+            .get::<GuildSettingsStore>()
+            .expect("Error getting GuildSettingsStore")
+            .write()
+            .await;
+
+        // This is also sent for every guild on startup, so rejoined guilds get marked active here
+        settings.join_guild(guild.id);
+    }
+
+    async fn guild_delete(&self, ctx: Context, incomplete: GuildUnavailable, _full: Option<Guild>) {
+        // Discord also sends this during outages, we only want to handle actually being removed
+        if incomplete.unavailable {
+            return;
+        }
+
+        let data = ctx.data.read().await;
+        let mut settings = data
+            .get::<GuildSettingsStore>()
+            .expect("Error getting GuildSettingsStore")
+            .write()
+            .await;
+
+        settings.leave_guild(incomplete.id);
+    }
+}
+
+/// Periodically purges the data of guilds we left more than `retention` ago
+pub async fn retention_job(cache: Arc<RwLock<GuildSettingsCache>>, retention: Duration) {
+    let mut interval = tokio::time::interval(PURGE_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let purged = cache.write().await.purge_departed_guilds(retention);
+
+        if purged != 0 {
+            println!("Purged data for {} departed guilds", purged);
+        }
+    }
+}
+
+/// Periodically writes changed guild settings to the database
+///
+/// A crash loses at most one `interval` of changes
+pub async fn flush_job(cache: Arc<RwLock<GuildSettingsCache>>, interval: Duration) {
+    let mut interval = tokio::time::interval(interval);
+
+    loop {
+        interval.tick().await;
+
+        cache.write().await.flush();
+    }
+}
@@ -0,0 +1,238 @@
+use std::{collections::HashMap, fmt::Display, sync::Arc};
+
+use lazy_static::lazy_static;
+use serenity::{
+    async_trait,
+    client::{Context, EventHandler},
+    model::{
+        id::{GuildId, UserId},
+        interactions::Interaction,
+    },
+    prelude::{RwLock, TypeMapKey},
+};
+use slashy::framework::CommandContext;
+
+use crate::bot::guild_settings::GuildSettingsStore;
+
+/// A language the bot's responses are translated into
+pub struct Locale {
+    /// The code used in the settings, matching the start of Discord's locale codes
+    pub code: &'static str,
+    /// The name of the language in that language
+    pub name: &'static str,
+    catalog: &'static str,
+}
+
+/// Every bundled locale, to add one translate `locales/en.json` and add it here
+pub const LOCALES: [Locale; 2] = [
+    Locale {
+        code: "en",
+        name: "English",
+        catalog: include_str!("../../locales/en.json"),
+    },
+    Locale {
+        code: "es",
+        name: "Español",
+        catalog: include_str!("../../locales/es.json"),
+    },
+];
+
+/// Used when neither the guild nor Discord give us a locale we have
+pub const DEFAULT_LOCALE: &str = "en";
+
+lazy_static! {
+    static ref CATALOGS: HashMap<&'static str, HashMap<String, String>> = LOCALES
+        .iter()
+        .map(|l| {
+            let catalog = serde_json::from_str(l.catalog)
+                .unwrap_or_else(|e| panic!("Error in the {} catalog: {}", l.code, e));
+            (l.code, catalog)
+        })
+        .collect();
+}
+
+/// Looks up a message in a locale's catalog and fills in its `{placeholders}`
+///
+/// Missing messages fall back to English, and then to the key itself
+pub fn translate(locale: &str, key: &str, args: &[(&str, &(dyn Display + Sync))]) -> String {
+    let message = CATALOGS
+        .get(locale)
+        .and_then(|c| c.get(key))
+        .or_else(|| CATALOGS[DEFAULT_LOCALE].get(key))
+        .map_or(key, |m| m.as_str());
+
+    // Filled in one pass so placeholders in the values, like in chained messages, are left alone
+    let mut result = String::with_capacity(message.len());
+    let mut rest = message;
+
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[.. start]);
+        rest = &rest[start ..];
+
+        let value = rest
+            .find('}')
+            .and_then(|end| args.iter().find(|(name, _)| *name == &rest[1 .. end]))
+            .map(|(name, value)| (name.len() + 2, value));
+
+        match value {
+            Some((len, value)) => {
+                result.push_str(&value.to_string());
+                rest = &rest[len ..];
+            }
+            None => {
+                result.push('{');
+                rest = &rest[1 ..];
+            }
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// Translates a message, `t!(locale, "key", name = value)` fills `{name}` with `value`
+#[macro_export]
+macro_rules! t {
+    ($locale:expr, $key:expr $(, $name:ident = $value:expr)* $(,)?) => {
+        $crate::bot::locale::translate(
+            $locale,
+            $key,
+            &[$((stringify!($name), &$value as &(dyn std::fmt::Display + Sync))),*],
+        )
+    };
+}
+
+/// Finds the bundled locale for a Discord locale, `es-ES` uses `es`
+pub fn find_locale(locale: &str) -> Option<&'static str> {
+    let language = locale.split('-').next().unwrap_or_default();
+
+    LOCALES
+        .iter()
+        .find(|l| l.code == locale || l.code == language)
+        .map(|l| l.code)
+}
+
+/// Picks the first of the locales we have a catalog for
+fn first_locale<'a>(locales: impl IntoIterator<Item = Option<&'a str>>) -> &'static str {
+    locales
+        .into_iter()
+        .flatten()
+        .find_map(find_locale)
+        .unwrap_or(DEFAULT_LOCALE)
+}
+
+/// The locale of the last interaction each user sent, slashy doesn't give commands the interaction
+pub struct UserLocales;
+
+impl TypeMapKey for UserLocales {
+    type Value = Arc<RwLock<HashMap<UserId, String>>>;
+}
+
+/// Remembers the locale of every interaction so commands can answer in it
+///
+/// Has to be registered before the commands so the locale is stored before they run
+pub struct LocaleHandler;
+
+#[async_trait]
+impl EventHandler for LocaleHandler {
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        let (user, locale) = match &interaction {
+            Interaction::ApplicationCommand(i) => (i.user.id, &i.locale),
+            Interaction::MessageComponent(i) => (i.user.id, &i.locale),
+            _ => return,
+        };
+
+        let data = ctx.data.read().await;
+        let mut locales = data.get::<UserLocales>().unwrap().write().await;
+
+        if locales.get(&user) != Some(locale) {
+            locales.insert(user, locale.clone());
+        }
+    }
+}
+
+/// The locale for a command's responses
+///
+/// This is the guild's locale setting, then the locale of the user's client, then the guild's
+/// Discord locale. Call this before taking the data lock for writing or it will deadlock
+pub async fn command_locale(ctx: &CommandContext) -> &'static str {
+    let data = ctx.ctx.data.read().await;
+
+    let setting = match ctx.guild_id() {
+        Some(g) => data.get::<GuildSettingsStore>().unwrap().read().await.locale(g),
+        None => None,
+    };
+    let user = match ctx.author() {
+        Some(u) => data.get::<UserLocales>().unwrap().read().await.get(&u.id).cloned(),
+        None => None,
+    };
+
+    drop(data);
+
+    let guild = match ctx.guild_id() {
+        Some(g) => ctx.ctx.cache.guild_field(g, |g| g.preferred_locale.clone()).await,
+        None => None,
+    };
+
+    first_locale(vec![setting.as_deref(), user.as_deref(), guild.as_deref()])
+}
+
+/// The locale for messages that aren't replies to an interaction, like chain responses
+///
+/// This is the guild's locale setting, then the guild's Discord locale
+pub async fn guild_locale(
+    ctx: &Context,
+    guild_id: GuildId,
+    setting: Option<&str>,
+) -> &'static str {
+    let guild = ctx.cache.guild_field(guild_id, |g| g.preferred_locale.clone()).await;
+
+    first_locale(vec![setting, guild.as_deref()])
+}
+
+/// The locale for responses to a component, the guild's setting then the interaction's locale
+pub fn interaction_locale(setting: Option<&str>, interaction: &str) -> &'static str {
+    first_locale(vec![setting, Some(interaction)])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    fn placeholders(message: &str) -> HashSet<&str> {
+        message
+            .split('{')
+            .skip(1)
+            .filter_map(|s| s.split('}').next())
+            .collect()
+    }
+
+    #[test]
+    fn every_locale_has_every_key() {
+        let english = &CATALOGS[DEFAULT_LOCALE];
+
+        for locale in &LOCALES {
+            let catalog = &CATALOGS[locale.code];
+
+            for (key, message) in english {
+                let translated = catalog
+                    .get(key)
+                    .unwrap_or_else(|| panic!("{} is missing {}", locale.code, key));
+
+                assert_eq!(
+                    placeholders(message),
+                    placeholders(translated),
+                    "{} has different placeholders in {}",
+                    locale.code,
+                    key
+                );
+            }
+
+            for key in catalog.keys() {
+                assert!(english.contains_key(key), "{} has unknown key {}", locale.code, key);
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+
+use serenity::{
+    client::Context,
+    futures::future::join_all,
+    model::id::{GuildId, UserId},
+};
+
+use crate::t;
+
+/// Resolves users to the names shown in responses
+///
+/// Names are remembered for as long as this lives, so make one for each response
+pub struct MemberNames<'a> {
+    ctx: &'a Context,
+    guild_id: Option<GuildId>,
+    locale: &'static str,
+    names: HashMap<UserId, String>,
+}
+
+impl<'a> MemberNames<'a> {
+    /// Without a guild users are named by their username
+    pub fn new(ctx: &'a Context, guild_id: Option<GuildId>, locale: &'static str) -> Self {
+        MemberNames {
+            ctx,
+            guild_id,
+            locale,
+            names: HashMap::new(),
+        }
+    }
+
+    /// Looks up every user we don't have a name for yet at the same time
+    pub async fn resolve(&mut self, users: impl IntoIterator<Item = UserId>) {
+        let mut missing = users
+            .into_iter()
+            .filter(|u| !self.names.contains_key(u))
+            .collect::<Vec<_>>();
+        missing.sort();
+        missing.dedup();
+
+        let (ctx, guild_id) = (self.ctx, self.guild_id);
+        let names = join_all(missing.iter().map(|u| lookup(ctx, guild_id, *u))).await;
+
+        for (user, name) in missing.into_iter().zip(names) {
+            let name = name.unwrap_or_else(|| t!(self.locale, "members.unknown_user"));
+            self.names.insert(user, name);
+        }
+    }
+
+    /// The name of a user, users that haven't been resolved are unknown
+    pub fn name(&self, user: UserId) -> String {
+        self.names
+            .get(&user)
+            .cloned()
+            .unwrap_or_else(|| t!(self.locale, "members.unknown_user"))
+    }
+
+    /// Resolves a single user's name
+    pub async fn get(&mut self, user: UserId) -> String {
+        self.resolve(Some(user)).await;
+        self.name(user)
+    }
+}
+
+/// A member's nickname, or their username if they have left the guild
+///
+/// Both lookups try the cache before asking Discord
+async fn lookup(ctx: &Context, guild_id: Option<GuildId>, user: UserId) -> Option<String> {
+    if let Some(g) = guild_id {
+        if let Ok(m) = g.member(ctx, user).await {
+            return Some(m.display_name().into_owned());
+        }
+    }
+
+    user.to_user(ctx).await.ok().map(|u| u.name)
+}
@@ -0,0 +1,15 @@
+use slashy::{commands::CommandResult, framework::CommandContext};
+
+/// Sends a response that can't ping anyone, for text written by users like names and prefixes
+///
+/// slashy's `send_str` can't set allowed mentions, so this sends a message instead
+pub async fn send_without_mentions(ctx: &CommandContext, content: &str) -> CommandResult {
+    ctx.send_message(|m| {
+        m.content(content);
+        m.allowed_mentions(|a| a.empty_parse());
+        m
+    })
+    .await?;
+
+    Ok(())
+}
@@ -1,2 +1,9 @@
+pub mod audit;
 pub mod commands;
+pub mod components;
 pub mod guild_settings;
+pub mod lifecycle;
+pub mod locale;
+pub mod members;
+pub mod mentions;
+pub mod permissions;
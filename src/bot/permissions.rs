@@ -0,0 +1,31 @@
+use serenity::{
+    client::Context,
+    model::{channel::GuildChannel, guild::Member},
+};
+use slashy::{commands::CommandResult, permissions_check};
+
+use crate::bot::guild_settings::GuildSettingsStore;
+
+/// Permission check that passes if the member is an administrator or has a bot manager role
+#[allow(non_snake_case)]
+#[permissions_check]
+pub async fn SETTINGS_MANAGER(
+    ctx: &Context,
+    member: &Member,
+    _channel: &GuildChannel,
+) -> CommandResult<bool> {
+    is_settings_manager(ctx, member).await
+}
+
+/// Whether a member is allowed to change the bot's settings
+pub async fn is_settings_manager(ctx: &Context, member: &Member) -> CommandResult<bool> {
+    if member.permissions(ctx).await?.administrator() {
+        return Ok(true);
+    }
+
+    let data = ctx.data.read().await;
+    let settings = data.get::<GuildSettingsStore>().unwrap().read().await;
+    let manager_roles = settings.manager_roles(member.guild_id);
+
+    Ok(member.roles.iter().any(|r| manager_roles.contains(r)))
+}
@@ -0,0 +1,121 @@
+use serenity::{
+    client::Context,
+    model::{channel::Message, id::UserId},
+};
+
+use super::styles::jump_link;
+use crate::{
+    bot::{guild_settings::GuildSettings, locale::guild_locale},
+    t,
+};
+
+/// The records a broken chain beat, worked out before it's added to the history
+pub struct ChainRecords {
+    /// The guild's previous longest chain, if this one beat it
+    pub server: Option<i32>,
+    /// The channel's previous longest chain, if this one beat it
+    pub channel: Option<i32>,
+    /// Everyone in the chain who beat their longest chain in the guild
+    pub personal_bests: Vec<UserId>,
+}
+
+/// Announces a running chain reaching one of the guild's milestones
+pub async fn announce_milestone(
+    ctx: &Context,
+    message: &Message,
+    length: u16,
+    settings: &GuildSettings,
+) {
+    if settings.milestones.contains(&length) {
+        let locale = message_locale(ctx, message, settings).await;
+
+        announce(
+            ctx,
+            message,
+            settings,
+            locale,
+            t!(locale, "announce.milestone", length = length),
+        )
+        .await;
+    }
+}
+
+/// Announces any records a broken chain beat
+pub async fn announce_records(
+    ctx: &Context,
+    message: &Message,
+    length: u16,
+    records: &ChainRecords,
+    settings: &GuildSettings,
+) {
+    let locale = message_locale(ctx, message, settings).await;
+    let mut lines = Vec::new();
+
+    // A server record is always a channel record too, so only mention the bigger one
+    if let Some(previous) = records.server {
+        lines.push(t!(
+            locale,
+            "announce.server_record",
+            length = length,
+            previous = previous
+        ));
+    } else if let Some(previous) = records.channel {
+        lines.push(t!(
+            locale,
+            "announce.channel_record",
+            channel = format!("<#{}>", message.channel_id.0),
+            length = length,
+            previous = previous
+        ));
+    }
+
+    if !records.personal_bests.is_empty() {
+        lines.push(t!(
+            locale,
+            "announce.personal_best",
+            users = records
+                .personal_bests
+                .iter()
+                .map(|u| format!("<@{}>", u.0))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+
+    if !lines.is_empty() {
+        announce(ctx, message, settings, locale, lines.join("\n")).await;
+    }
+}
+
+/// The locale announcements about a chain are sent in
+async fn message_locale(
+    ctx: &Context,
+    message: &Message,
+    settings: &GuildSettings,
+) -> &'static str {
+    guild_locale(ctx, message.guild_id.unwrap(), settings.locale.as_deref()).await
+}
+
+/// Posts in the announcement channel, or the chain's channel if the guild doesn't have one
+async fn announce(
+    ctx: &Context,
+    message: &Message,
+    settings: &GuildSettings,
+    locale: &str,
+    content: String,
+) {
+    let channel = settings.announce_channel.unwrap_or(message.channel_id);
+
+    let result = channel
+        .send_message(&ctx, |m| {
+            m.content(format!("{}{}", content, jump_link(message, channel, locale)));
+            // Mentions are only there to show names, nobody should get pinged for a record
+            m.allowed_mentions(|a| a.empty_parse());
+            m
+        })
+        .await;
+
+    if let Err(e) = result {
+        println!("Error sending chain announcement: {:?}", e);
+    }
+}
@@ -0,0 +1,176 @@
+use ab_glyph::{point, Font, FontRef, PxScale, ScaleFont};
+use lazy_static::lazy_static;
+use tiny_skia::{Color, Paint, Pixmap, Rect, Transform};
+
+use super::styles::ChainSummary;
+use crate::t;
+
+/// DejaVu Sans, see assets/DejaVuSans-LICENSE.txt
+static FONT_DATA: &[u8] = include_bytes!("../../assets/DejaVuSans.ttf");
+
+lazy_static! {
+    static ref FONT: FontRef<'static> =
+        FontRef::try_from_slice(FONT_DATA).expect("Error loading the card font");
+}
+
+const WIDTH: u32 = 800;
+const PADDING: f32 = 30.0;
+const ROW_HEIGHT: f32 = 40.0;
+/// The most participants shown before the rest are summed up in one line
+const MAX_ROWS: usize = 10;
+
+const BACKGROUND: [u8; 3] = [47, 49, 54];
+const TEXT: [u8; 3] = [255, 255, 255];
+const SUBTEXT: [u8; 3] = [185, 187, 190];
+const BAR: [u8; 3] = [120, 5, 90];
+
+/// Renders a PNG card showing a chain's length, text and everyone's points
+///
+/// Returns None if the image couldn't be encoded
+pub fn render_card(summary: &ChainSummary) -> Option<Vec<u8>> {
+    let rows = summary.points.len().min(MAX_ROWS);
+    let extra = summary.points.len() > MAX_ROWS;
+    let height = PADDING * 2.0 + 110.0 + ROW_HEIGHT * (rows + extra as usize) as f32;
+
+    let mut pixmap = Pixmap::new(WIDTH, height as u32)?;
+    pixmap.fill(Color::from_rgba8(BACKGROUND[0], BACKGROUND[1], BACKGROUND[2], 255));
+
+    let max_width = WIDTH as f32 - PADDING * 2.0;
+
+    draw_text(
+        &mut pixmap,
+        &t!(summary.locale, "style.title", length = summary.length),
+        44.0,
+        PADDING,
+        PADDING,
+        TEXT,
+    );
+    draw_text(
+        &mut pixmap,
+        &fit_text(&summary.content, 26.0, max_width),
+        26.0,
+        PADDING,
+        PADDING + 60.0,
+        SUBTEXT,
+    );
+
+    let name_width = 220.0;
+    let bar_x = PADDING + name_width + 10.0;
+    let bar_space = max_width - name_width - 130.0;
+    let most_points = summary.points.first().map_or(1, |(_, p)| (*p).max(1));
+
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(BAR[0], BAR[1], BAR[2], 255);
+
+    for (i, (name, points)) in summary.points.iter().take(MAX_ROWS).enumerate() {
+        let y = PADDING + 110.0 + ROW_HEIGHT * i as f32;
+
+        draw_text(&mut pixmap, &fit_text(name, 22.0, name_width), 22.0, PADDING, y, TEXT);
+
+        let bar_width = (bar_space * *points as f32 / most_points as f32).max(4.0);
+        if let Some(rect) = Rect::from_xywh(bar_x, y + 4.0, bar_width, ROW_HEIGHT - 16.0) {
+            pixmap.fill_rect(rect, &paint, Transform::identity(), None);
+        }
+
+        draw_text(
+            &mut pixmap,
+            &t!(summary.locale, "card.points", points = points),
+            20.0,
+            bar_x + bar_width + 10.0,
+            y + 2.0,
+            SUBTEXT,
+        );
+    }
+
+    if extra {
+        draw_text(
+            &mut pixmap,
+            &t!(summary.locale, "card.more", count = summary.points.len() - MAX_ROWS),
+            20.0,
+            PADDING,
+            PADDING + 110.0 + ROW_HEIGHT * rows as f32,
+            SUBTEXT,
+        );
+    }
+
+    pixmap.encode_png().ok()
+}
+
+/// Measures how wide some text would be when drawn
+fn text_width(text: &str, size: f32) -> f32 {
+    let font = FONT.as_scaled(PxScale::from(size));
+    let mut width = 0.0;
+    let mut last = None;
+
+    for c in text.chars() {
+        let id = font.glyph_id(c);
+        if let Some(last) = last {
+            width += font.kern(last, id);
+        }
+        width += font.h_advance(id);
+        last = Some(id);
+    }
+
+    width
+}
+
+/// Cuts text down with an ellipsis so it fits in `max_width`
+fn fit_text(text: &str, size: f32, max_width: f32) -> String {
+    if text_width(text, size) <= max_width {
+        return text.to_owned();
+    }
+
+    let mut text = text.chars().collect::<Vec<_>>();
+    while !text.is_empty() {
+        text.pop();
+        let fitted = format!("{}…", text.iter().collect::<String>());
+        if text_width(&fitted, size) <= max_width {
+            return fitted;
+        }
+    }
+
+    String::new()
+}
+
+/// Draws a line of text with its top left corner at `x`, `y`
+///
+/// The background is always opaque so glyphs are blended straight into the pixels
+fn draw_text(pixmap: &mut Pixmap, text: &str, size: f32, x: f32, y: f32, color: [u8; 3]) {
+    let font = FONT.as_scaled(PxScale::from(size));
+    let width = pixmap.width() as i32;
+    let height = pixmap.height() as i32;
+    let data = pixmap.data_mut();
+
+    let mut caret = x;
+    let mut last = None;
+
+    for c in text.chars() {
+        let id = font.glyph_id(c);
+        if let Some(last) = last {
+            caret += font.kern(last, id);
+        }
+        let glyph = id.with_scale_and_position(size, point(caret, y + font.ascent()));
+        caret += font.h_advance(id);
+        last = Some(id);
+
+        let outline = match FONT.outline_glyph(glyph) {
+            Some(o) => o,
+            None => continue,
+        };
+        let bounds = outline.px_bounds();
+
+        outline.draw(|gx, gy, coverage| {
+            let px = bounds.min.x as i32 + gx as i32;
+            let py = bounds.min.y as i32 + gy as i32;
+            if px < 0 || py < 0 || px >= width || py >= height {
+                return;
+            }
+
+            let i = ((py * width + px) * 4) as usize;
+            for (c, channel) in color.iter().enumerate() {
+                let old = data[i + c] as f32;
+                data[i + c] = (*channel as f32 * coverage + old * (1.0 - coverage)) as u8;
+            }
+        });
+    }
+}
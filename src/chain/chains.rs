@@ -1,9 +1,9 @@
-use std::{cmp::min, collections::HashMap};
+use std::{cmp::max, collections::HashMap};
 
 use serenity::{
     async_trait,
     client::{Context, EventHandler},
-    futures::future::try_join_all,
+    futures::future::join_all,
     model::{
         channel::{Channel, GuildChannel, Message},
         guild::Member,
@@ -14,15 +14,26 @@ use serenity::{
 use tokio::join;
 
 use crate::{
-    bot::guild_settings::{GuildSettings, GuildSettingsStore},
-    chain::styles::embed_style,
-    database::{tables::leaderboards::update_server_longest_chains, update_longest_chains},
+    bot::{
+        commands::OptOutStore,
+        guild_settings::{GuildSettings, GuildSettingsStore},
+        locale::guild_locale,
+    },
+    database::{
+        tables::{
+            history::{get_longest_chain, record_chain},
+            leaderboards::{get_server_longest_chain, update_server_longest_chains},
+        },
+        update_longest_chains,
+    },
     DatabaseConn,
 };
 
 use super::{
+    announcements::{announce_milestone, announce_records, ChainRecords},
     points::{give_points, points_per_user},
-    styles::{classic_style, text_style},
+    replay::replay_chain,
+    styles::{send_embed, send_text, ChainSummary, STYLES},
 };
 
 pub struct ChainCounter;
@@ -54,21 +65,48 @@ impl EventHandler for ChainHandler {
         // Get the chain cache
         let mut data = ctx.data.write().await;
 
-        let chains = data
-        .get_mut::<ChainCounter>()
-        // If we can't get this something has gone horribly wrong and a panic is justified
-            .expect("Error getting ChainCounter from Context");
+        // Opted out users don't take part in chains at all
+        if data
+            .get::<OptOutStore>()
+            .map_or(false, |o| o.contains(&message.author.id))
+        {
+            return;
+        }
 
         // Store the ids we use a lot
         let author_id = message.author.id;
         let channel_id = message.channel_id;
+        let guild_id = message.guild_id.unwrap();
+
+        // Get the settings for this channel with any of its overrides applied
+        let settings = data
+            .get::<GuildSettingsStore>()
+            .expect("Error getting GuildSettingsStore")
+            .read()
+            .await
+            .get_or_default(guild_id)
+            .for_channel(channel_id);
+
+        let chains = data
+        .get_mut::<ChainCounter>()
+        // If we can't get this something has gone horribly wrong and a panic is justified
+            .expect("Error getting ChainCounter from Context");
 
         if !chains.contains_key(&channel_id) {
             // If we do not already have a chain in that channel, make a new chain
-            create_chain(&message, &ctx, chains).await;
-        } else if chains.get(&channel_id).unwrap().message == message.content {
+            create_chain(&message, &ctx, chains, &settings).await;
+        } else if settings
+            .match_mode
+            .matches(&chains.get(&channel_id).unwrap().message, &message.content)
+        {
             // If we are continuing the chain, update it and write the changes
             let chain = chains.get_mut(&channel_id).unwrap();
+
+            let last_author = chain.msg_cache.last().map(|m| m.author.id);
+            if !can_continue(&settings, last_author, author_id) {
+                return;
+            }
+
             chain.length += 1;
             chain.msg_cache.push(message.clone());
 
@@ -79,6 +117,12 @@ impl EventHandler for ChainHandler {
             } else {
                 *chain.num_messages.get_mut(&author_id).unwrap() += 1
             }
+
+            // Don't hold the lock on the chains while we send the announcement
+            let length = chain.length;
+            drop(data);
+
+            announce_milestone(&ctx, &message, length, &settings).await;
         } else {
             // If we are breaking the chain, get the points and cleanup the chain
             let chain = chains.get(&channel_id).unwrap().clone();
@@ -92,26 +136,37 @@ impl EventHandler for ChainHandler {
             // Manually drop chains as we need data for the final step
             drop(chains);
 
-            let settings_store = data
-                .get::<GuildSettingsStore>()
-                .expect("Error getting GuildSettingsStore")
-                .read()
-                .await;
-
-            let guild_settings = settings_store.get_or_default(message.guild_id.unwrap());
-
             // And update points and user info
             join!(
-                give_points(&points, &data, message.guild_id.unwrap()),
-                update_chain_data(&chain, &data, message.guild_id.unwrap()),
-                cleanup_chain(&chain, &message, &ctx),
-                create_chain_response(&chain, &points, &message, &ctx, &guild_settings)
+                give_points(&points, &data, guild_id),
+                async {
+                    let records =
+                        update_chain_data(&chain, &points, &message, &data, guild_id).await;
+                    // Chains too short for a response don't get announced either
+                    if chain.length > settings.chain_threshold {
+                        announce_records(&ctx, &message, chain.length, &records, &settings).await;
+                    }
+                },
+                cleanup_chain(&chain, &message, &ctx, &settings),
+                create_chain_response(&chain, &points, &message, &ctx, &settings)
             );
         }
     }
 }
 
-async fn create_chain(message: &Message, ctx: &Context, chains: &mut ChainStore) {
+/// Whether a message from `author` can follow one from `last_author` in a chain
+///
+/// When members have to alternate nobody can add to a chain twice in a row
+fn can_continue(settings: &GuildSettings, last_author: Option<UserId>, author: UserId) -> bool {
+    !settings.alternate_member || last_author != Some(author)
+}
+
+async fn create_chain(
+    message: &Message,
+    ctx: &Context,
+    chains: &mut ChainStore,
+    settings: &GuildSettings,
+) {
     let channel_id = message.channel_id;
     let author_id = message.author.id;
 
@@ -128,7 +183,12 @@ async fn create_chain(message: &Message, ctx: &Context, chains: &mut ChainStore)
                 .await
                 .expect("Error getting messages");
             let msg = messages.get(1).unwrap();
-            if message.content == msg.content && !msg.author.bot {
+
+            if !can_continue(settings, Some(msg.author.id), author_id) {
+                return;
+            }
+
+            if settings.match_mode.matches(&msg.content, &message.content) && !msg.author.bot {
                 let mut num_messages = HashMap::new();
                 if msg.author.id == author_id {
                     num_messages.insert(author_id, 2);
@@ -167,38 +227,79 @@ async fn create_chain_response(
     ctx: &Context,
     settings: &GuildSettings,
 ) {
-    if chain.length > settings.chain_threshold {
-        if settings.style == "classic" {
-            classic_style(chain, message, ctx).await
-        } else if settings.style == "embed" {
-            embed_style(chain, points, message, ctx).await;
-        } else if settings.style == "text" {
-            text_style(chain, points, message, ctx).await;
-        } else {
-            match message.channel_id.to_channel(&ctx).await.unwrap() {
-                Channel::Guild(g) => {
-                    let perms = g
-                        .permissions_for_user(&ctx, ctx.http.get_current_user().await.unwrap().id)
-                        .await
-                        .unwrap();
-                    if perms.embed_links() {
-                        embed_style(chain, points, message, ctx).await;
-                    } else {
-                        text_style(chain, points, message, ctx).await;
-                    }
-                }
-                Channel::Private(_) => {
-                    text_style(chain, points, message, ctx).await;
-                }
-                _ => unreachable!(),
+    if chain.length <= settings.chain_threshold {
+        return;
+    }
+
+    let mut style = STYLES
+        .get(settings.style.name())
+        .unwrap_or_else(|| STYLES.fallback());
+
+    // Styles that don't post a message only touch the breaking message in the chain's channel
+    let mut channel = match settings.announce_channel {
+        Some(c) if style.posts_message() => c,
+        _ => message.channel_id,
+    };
+
+    // Fall back if we're missing the permissions the style needs
+    let target = match channel.to_channel(&ctx).await {
+        Ok(c) => c,
+        Err(e) => {
+            println!("Error getting announcement channel {}: {:?}", channel, e);
+            channel = message.channel_id;
+            message.channel_id.to_channel(&ctx).await.unwrap()
+        }
+    };
+
+    match target {
+        Channel::Guild(g) => {
+            let perms = g
+                .permissions_for_user(&ctx, ctx.http.get_current_user().await.unwrap().id)
+                .await
+                .unwrap();
+            if !perms.contains(style.required_permissions()) {
+                style = STYLES.fallback();
             }
         }
+        Channel::Private(_) => style = STYLES.fallback(),
+        _ => unreachable!(),
+    }
+
+    let locale = guild_locale(ctx, message.guild_id.unwrap(), settings.locale.as_deref()).await;
+    let summary = ChainSummary::new(chain, points, message, ctx, locale).await;
+
+    // Logged responses only leave reactions in the chain's channel
+    if let (true, Some(log_channel)) = (settings.log_responses, settings.log_channel) {
+        let can_embed = match log_channel.to_channel(&ctx).await {
+            Ok(Channel::Guild(g)) => g
+                .permissions_for_user(&ctx, ctx.http.get_current_user().await.unwrap().id)
+                .await
+                .map_or(false, |p| p.embed_links()),
+            _ => false,
+        };
+
+        let sent = if can_embed {
+            send_embed(ctx, message, &summary, log_channel, false).await
+        } else {
+            send_text(ctx, message, &summary, log_channel, false).await
+        };
+        if let Err(e) = sent {
+            println!("Error sending chain response to the log channel: {:?}", e);
+        }
+
+        if style.posts_message() {
+            return;
+        }
+    }
+
+    if let Err(e) = style.send(ctx, message, &summary, settings, channel).await {
+        println!("Error sending {} chain response: {:?}", style.name(), e);
     }
 }
 
-async fn cleanup_chain(chain: &Chain, message: &Message, ctx: &Context) {
+async fn cleanup_chain(chain: &Chain, message: &Message, ctx: &Context, settings: &GuildSettings) {
     println!("cleanup_chain {}", chain.length);
-    if chain.length < 5 {
+    if !settings.remove_messages || chain.length < 5 {
         return;
     }
 
@@ -217,34 +318,122 @@ async fn cleanup_chain(chain: &Chain, message: &Message, ctx: &Context) {
 
         println!("Deleting {:?} from users {:?}", ids.len(), user_map);
 
-        mass_delete(ctx, ids, c).await;
+        // Everyone only sent one message, there's nothing to remove or replay
+        if ids.is_empty() {
+            return;
+        }
+
+        mass_delete(ctx, ids, &c).await;
+
+        // Stand in for the removed messages so the chain isn't lost from the channel
+        if settings.replay_chains {
+            if let Err(e) = replay_chain(ctx, chain, &c, settings).await {
+                println!("Error replaying chain in {}: {:?}", c.id, e);
+            }
+        }
     }
 }
 
-async fn update_chain_data(chain: &Chain, data: &TypeMap, guild_id: GuildId) {
+async fn update_chain_data(
+    chain: &Chain,
+    points: &HashMap<UserId, u64>,
+    breaking_message: &Message,
+    data: &TypeMap,
+    guild_id: GuildId,
+) -> ChainRecords {
     let database = data.get::<DatabaseConn>().unwrap().lock().await;
+    let length = chain.length as i32;
+
+    // Look up the records before this chain is added to the history
+    let history = get_longest_chain(&database, guild_id, None);
+    let channel = get_longest_chain(&database, guild_id, Some(breaking_message.channel_id));
 
+    // Chains from before the history was kept are only on the leaderboard
+    let leaderboard = get_server_longest_chain(&database, guild_id);
+    let server = max(history, leaderboard);
+    // The leaderboard doesn't know about channels, so wait until the history has caught up
+    let channel = if history >= leaderboard { channel } else { 0 };
+
+    let mut personal_bests = Vec::new();
     for user in &chain.chainers {
-        update_longest_chains(&database, *user, chain.length as i32);
-        update_server_longest_chains(&database, guild_id, *user, chain.length as i32);
+        update_longest_chains(&database, *user, length);
+        if update_server_longest_chains(&database, guild_id, *user, length) {
+            personal_bests.push(*user);
+        }
+    }
+
+    record_chain(
+        &database,
+        guild_id,
+        breaking_message.channel_id,
+        chain,
+        breaking_message.author.id,
+        points,
+    );
+
+    // The first chain in a guild or channel isn't worth celebrating
+    ChainRecords {
+        server: Some(server).filter(|s| *s > 0 && length > *s),
+        channel: Some(channel).filter(|c| *c > 0 && length > *c),
+        personal_bests,
     }
 }
 
+/// Bulk delete any number of messages
+async fn mass_delete(ctx: &Context, messages: Vec<MessageId>, channel: &GuildChannel) {
+    // Discord bulk deletes at most 100 messages at a time
+    let deletes = messages
+        .chunks(100)
+        .map(|m| channel.delete_messages(&ctx, m));
+
+    // Messages we couldn't delete are left in the channel
+    for result in join_all(deletes).await {
+        if let Err(e) = result {
+            println!("Error deleting chain messages in {}: {:?}", channel.id, e);
+        }
+    }
+}
 
-/// Bulk delete any number of messages over 2
-async fn mass_delete(ctx: &Context, messages: Vec<MessageId>, channel: GuildChannel) {
-    if messages.len() <= 100 {
-        channel.delete_messages(&ctx, messages).await.unwrap()
-    } else {
-        let mut messages = messages.split_at(100);
-        let mut futures = Vec::new();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::guild_settings::{ChannelSettings, DEFAULT_SETTINGS};
+
+    const AUTHOR: UserId = UserId(10);
+    const OTHER: UserId = UserId(11);
+
+    /// The settings for an overridden channel and for a channel using the guild's setting
+    fn channel_settings(guild: bool, channel: bool) -> (GuildSettings, GuildSettings) {
+        let mut settings = DEFAULT_SETTINGS.clone();
+        settings.alternate_member = guild;
+        settings
+            .channel_overrides
+            .insert(ChannelId(1), ChannelSettings {
+                alternate_member: Some(channel),
+                ..Default::default()
+            });
+
+        (
+            settings.for_channel(ChannelId(1)),
+            settings.for_channel(ChannelId(2)),
+        )
+    }
 
-        while messages.1.len() != 0 {
-            futures.push(channel.delete_messages(&ctx, messages.0));
+    #[test]
+    fn channel_override_allows_chaining_twice() {
+        let (overridden, guild) = channel_settings(true, false);
 
-            messages = messages.1.split_at(min(messages.1.len(), 100))
-        }
+        assert!(can_continue(&overridden, Some(AUTHOR), AUTHOR));
+        assert!(!can_continue(&guild, Some(AUTHOR), AUTHOR));
+        assert!(can_continue(&guild, Some(OTHER), AUTHOR));
+    }
+
+    #[test]
+    fn channel_override_requires_alternating() {
+        let (overridden, guild) = channel_settings(false, true);
 
-        try_join_all(futures).await.unwrap();
+        assert!(!can_continue(&overridden, Some(AUTHOR), AUTHOR));
+        assert!(can_continue(&overridden, Some(OTHER), AUTHOR));
+        assert!(can_continue(&guild, Some(AUTHOR), AUTHOR));
     }
 }
@@ -1,4 +1,8 @@
+mod announcements;
+mod card;
 mod chains;
 pub use chains::*;
 pub mod points;
-mod styles;
+mod replay;
+pub mod styles;
+pub mod template;
@@ -0,0 +1,84 @@
+use serde_json::Value;
+use serenity::{
+    builder::CreateAllowedMentions,
+    client::Context,
+    model::{channel::GuildChannel, webhook::Webhook},
+    utils::hashmap_to_json_map,
+};
+
+use super::{template::MAX_MESSAGE_LENGTH, Chain};
+use crate::{
+    bot::{guild_settings::GuildSettings, locale::guild_locale, members::MemberNames},
+    t,
+};
+
+/// The name of the webhook we make in channels to replay chains through
+const WEBHOOK_NAME: &str = "Chain Replay";
+
+/// Posts a single message in place of a chain's removed messages
+///
+/// It's sent through a webhook with the starter's name and avatar so it looks like the chain
+pub async fn replay_chain(
+    ctx: &Context,
+    chain: &Chain,
+    channel: &GuildChannel,
+    settings: &GuildSettings,
+) -> serenity::Result<()> {
+    let locale = guild_locale(ctx, channel.guild_id, settings.locale.as_deref()).await;
+
+    let mut names = MemberNames::new(ctx, Some(channel.guild_id), locale);
+    names.resolve(chain.chainers.iter().copied()).await;
+    let participants = chain
+        .chainers
+        .iter()
+        .map(|u| names.name(*u))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    // The chain's text is cut short rather than the counter if it's too long
+    let counter = t!(locale, "replay.counter", length = chain.length);
+    let footer = t!(locale, "replay.participants", participants = participants);
+    let space = MAX_MESSAGE_LENGTH.saturating_sub(counter.chars().count() + footer.chars().count());
+    let content = chain
+        .message
+        .chars()
+        .take(space)
+        .chain(counter.chars())
+        .chain(footer.chars())
+        .take(MAX_MESSAGE_LENGTH)
+        .collect::<String>();
+
+    // The chained text and names shouldn't ping anyone
+    let mut mentions = CreateAllowedMentions::default();
+    mentions.empty_parse();
+    let mentions = Value::Object(hashmap_to_json_map(mentions.0));
+
+    let webhook = replay_webhook(ctx, channel).await?;
+
+    webhook
+        .execute(&ctx.http, false, |w| {
+            w.content(content);
+            w.username(chain.starter.display_name());
+            w.avatar_url(chain.starter.user.face());
+            // Workaround: the webhook builder has no allowed_mentions method in this serenity
+            w.0.insert("allowed_mentions", mentions);
+            w
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Finds the webhook we replay chains through in a channel, making it if it isn't there
+async fn replay_webhook(ctx: &Context, channel: &GuildChannel) -> serenity::Result<Webhook> {
+    let existing = channel
+        .webhooks(&ctx.http)
+        .await?
+        .into_iter()
+        .find(|w| w.name.as_deref() == Some(WEBHOOK_NAME) && w.token.is_some());
+
+    match existing {
+        Some(w) => Ok(w),
+        None => channel.create_webhook(&ctx.http, WEBHOOK_NAME).await,
+    }
+}
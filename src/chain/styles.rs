@@ -1,166 +1,553 @@
 use std::{borrow::Cow, collections::HashMap};
 
+use lazy_static::lazy_static;
 use serenity::{
+    async_trait,
     client::Context,
-    futures::future::join_all,
-    model::{channel::Message, id::UserId},
+    builder::CreateMessage,
+    http::AttachmentType,
+    model::{
+        channel::{Message, ReactionType},
+        id::{ChannelId, UserId},
+        Permissions,
+    },
     utils::Color,
 };
 
-use super::Chain;
+use super::{
+    card::render_card,
+    template::{render_template, MAX_MESSAGE_LENGTH},
+    Chain,
+};
+use crate::{
+    bot::{guild_settings::GuildSettings, members::MemberNames},
+    t,
+};
 
-pub async fn embed_style(
-    chain: &Chain,
-    points: &HashMap<UserId, u64>,
-    message: &Message,
-    ctx: &Context,
-) {
-    let guild = message.guild(&ctx).await.unwrap();
-    let breaker = message
-        .member
-        .clone()
-        .unwrap()
-        .nick
-        .unwrap_or(message.author.name.clone());
-
-    let user = ctx.http.get_current_user().await.unwrap().id;
-    let member = guild.member(ctx, user).await.unwrap();
-    let color = member
-        .colour(ctx)
-        .await
-        .unwrap_or(Color::from_rgb(120, 5, 90));
+lazy_static! {
+    /// Every style chain responses can be sent in
+    pub static ref STYLES: StyleRegistry = StyleRegistry::new();
+}
 
-    let mut members = Vec::new();
+/// A way of announcing a broken chain
+///
+/// To add a style implement this and add it to `StyleRegistry::new`,
+/// it can then be picked with `/settings set style`
+#[async_trait]
+pub trait ResponseStyle: Send + Sync {
+    /// The name used to pick the style in the settings
+    fn name(&self) -> &'static str;
 
-    for m_id in &chain.chainers {
-        members.push(guild.member(&ctx, m_id));
+    /// Permissions the bot needs in the channel to use the style
+    ///
+    /// When the bot is missing them the fallback style is used instead
+    fn required_permissions(&self) -> Permissions {
+        Permissions::empty()
     }
 
-    let mut members = join_all(members)
-        .await
-        .into_iter()
-        .filter_map(|m| m.map(|m| Some(m)).unwrap_or(None))
-        .collect::<Vec<_>>();
+    /// Whether the style posts a message, styles that don't only touch the breaking message
+    fn posts_message(&self) -> bool {
+        true
+    }
+
+    /// Sends the response to `channel`, which is the announcement channel if the guild has one
+    async fn send(
+        &self,
+        ctx: &Context,
+        message: &Message,
+        summary: &ChainSummary,
+        settings: &GuildSettings,
+        channel: ChannelId,
+    ) -> serenity::Result<()>;
+}
+
+pub struct StyleRegistry {
+    styles: Vec<Box<dyn ResponseStyle>>,
+}
+
+impl StyleRegistry {
+    fn new() -> Self {
+        StyleRegistry {
+            styles: vec![
+                Box::new(EmbedStyle),
+                Box::new(ClassicStyle),
+                Box::new(TextStyle),
+                Box::new(TemplateStyle),
+                Box::new(ImageStyle),
+                Box::new(ReactStyle),
+            ],
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn ResponseStyle> {
+        self.styles
+            .iter()
+            .find(|s| s.name() == name)
+            .map(|s| s.as_ref())
+    }
+
+    /// The style used when the chosen style can't be used, it needs no extra permissions
+    pub fn fallback(&self) -> &dyn ResponseStyle {
+        &TextStyle
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.styles.iter().map(|s| s.name())
+    }
+}
+
+/// Everything a style needs to know about a broken chain, with members resolved to names
+pub struct ChainSummary {
+    /// The locale the response is sent in
+    pub locale: &'static str,
+    pub length: u16,
+    pub content: String,
+    pub starter: String,
+    pub breaker: String,
+    /// The names of everyone in the chain, in the order they joined
+    pub participants: Vec<String>,
+    /// Everyone in the chain, in the same order as `participants`
+    pub participant_ids: Vec<UserId>,
+    /// The names of everyone who got points, with the most points first
+    pub points: Vec<(String, u64)>,
+}
+
+impl ChainSummary {
+    pub async fn new(
+        chain: &Chain,
+        points: &HashMap<UserId, u64>,
+        message: &Message,
+        ctx: &Context,
+        locale: &'static str,
+    ) -> ChainSummary {
+        // Members can leave mid chain, so this falls back to their username
+        let mut names = MemberNames::new(ctx, message.guild_id, locale);
+        names
+            .resolve(
+                chain
+                    .chainers
+                    .iter()
+                    .chain(points.keys())
+                    .chain(Some(&message.author.id))
+                    .copied(),
+            )
+            .await;
+        let name = |id: &UserId| names.name(*id);
+
+        let mut points = points
+            .iter()
+            .map(|(id, p)| (name(id), *p))
+            .collect::<Vec<_>>();
+        points.sort_by(|a, b| b.1.cmp(&a.1));
+
+        ChainSummary {
+            locale,
+            length: chain.length,
+            content: chain.message.clone(),
+            starter: chain.starter.display_name().into_owned(),
+            breaker: name(&message.author.id),
+            participants: chain.chainers.iter().map(name).collect(),
+            participant_ids: chain.chainers.clone(),
+            points,
+        }
+    }
+
+    /// Each user's points on their own line
+    pub fn points_list(&self) -> String {
+        self.points
+            .iter()
+            .map(|(name, p)| t!(self.locale, "style.points_line", name = name, points = p))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// A line linking back to the breaking message, for responses sent outside the chain's channel
+pub(super) fn jump_link(message: &Message, channel: ChannelId, locale: &str) -> String {
+    if channel == message.channel_id {
+        String::new()
+    } else {
+        format!(
+            "\n{}",
+            t!(
+                locale,
+                "style.jump_link",
+                channel = format!("<#{}>", message.channel_id.0),
+                link = message.link()
+            )
+        )
+    }
+}
+
+/// A line mentioning everyone in the chain, if the guild wants them pinged
+fn ping_line(summary: &ChainSummary, ping: bool) -> String {
+    if ping && !summary.participant_ids.is_empty() {
+        let mentions = summary
+            .participant_ids
+            .iter()
+            .map(|u| format!("<@{}>", u.0))
+            .collect::<Vec<_>>();
+        format!("\n{}", mentions.join(" "))
+    } else {
+        String::new()
+    }
+}
 
-    if !chain.chainers.contains(&message.author.id) {
-        members.push(message.member(&ctx).await.unwrap());
+/// Only lets a response ping the chain's participants, and only if the guild wants them pinged
+///
+/// Names and chained messages come from members, so nothing else in a response should ping
+fn set_allowed_mentions(m: &mut CreateMessage<'_>, summary: &ChainSummary, ping: bool) {
+    m.allowed_mentions(|a| {
+        a.empty_parse();
+        if ping {
+            a.users(summary.participant_ids.iter().copied());
+        }
+        a
+    });
+}
+
+struct EmbedStyle;
+
+#[async_trait]
+impl ResponseStyle for EmbedStyle {
+    fn name(&self) -> &'static str {
+        "embed"
+    }
+
+    fn required_permissions(&self) -> Permissions {
+        Permissions::EMBED_LINKS
+    }
+
+    async fn send(
+        &self,
+        ctx: &Context,
+        message: &Message,
+        summary: &ChainSummary,
+        settings: &GuildSettings,
+        channel: ChannelId,
+    ) -> serenity::Result<()> {
+        send_embed(ctx, message, summary, channel, settings.ping_participants).await
     }
+}
 
-    message
-        .channel_id
+/// Sends the embed response to a channel
+///
+/// When that isn't the chain's channel the embed links back to the breaking message,
+/// mentions in embeds never ping so participants are pinged in the message content
+pub async fn send_embed(
+    ctx: &Context,
+    message: &Message,
+    summary: &ChainSummary,
+    channel: ChannelId,
+    ping: bool,
+) -> serenity::Result<()> {
+    let guild = message.guild(&ctx).await.unwrap();
+    let user = ctx.http.get_current_user().await?.id;
+    let member = guild.member(ctx, user).await?;
+    let color = member
+        .colour(ctx)
+        .await
+        .unwrap_or(Color::from_rgb(120, 5, 90));
+    let locale = summary.locale;
+
+    channel
         .send_message(&ctx, |m| {
             m.embed(|e| {
-                e.title(format!("{} chain!", chain.length));
-                e.description(format!(
-                    "{} made a chain of {}",
-                    members
-                        .iter()
-                        .map(|m| m.display_name())
-                        .collect::<Vec<Cow<String>>>()
-                        .iter()
-                        .enumerate()
-                        .fold(String::new(), |mut acc, (i, v)| {
-                            acc.push_str(&format!(
-                                "{}{}",
-                                v,
-                                if i == members.len() - 1 { "" } else { ", " }
-                            ));
-                            acc
-                        }),
-                    chain.length
+                e.title(t!(locale, "style.title", length = summary.length));
+                e.description(t!(
+                    locale,
+                    "style.description",
+                    participants = summary.participants.join(", "),
+                    length = summary.length
                 ));
                 e.color(color);
-                e.field("starter", chain.starter.display_name(), true);
-                e.field("breaker", breaker, true);
-                e.field(
-                    "points",
-                    points
-                        .iter()
-                        .map(|(id, p)| {
-                            let member =
-                                members.iter().filter(|m| &m.user.id == id).next().unwrap();
-                            (member.display_name(), p)
-                        })
-                        .enumerate()
-                        .fold(String::new(), |mut str, (i, (m, p))| {
-                            str.push_str(&format!(
-                                "{}: {} points{}",
-                                m,
-                                p,
-                                if i == points.len() - 1 { "" } else { "\n" }
-                            ));
-                            str
-                        }),
-                    false,
-                );
+                e.field(t!(locale, "style.starter"), &summary.starter, true);
+                e.field(t!(locale, "style.breaker"), &summary.breaker, true);
+                e.field(t!(locale, "style.points"), summary.points_list(), false);
+                if channel != message.channel_id {
+                    e.field(
+                        t!(locale, "style.channel"),
+                        format!(
+                            "<#{}> [{}]({})",
+                            message.channel_id.0,
+                            t!(locale, "style.jump"),
+                            message.link()
+                        ),
+                        false,
+                    );
+                }
                 e
             });
+            if ping {
+                m.content(ping_line(summary, ping).trim_start());
+            }
+            set_allowed_mentions(m, summary, ping);
             m
         })
-        .await
-        .unwrap();
+        .await?;
+
+    Ok(())
 }
 
-pub async fn text_style(
-    chain: &Chain,
-    points: &HashMap<UserId, u64>,
-    message: &Message,
+/// Sends the plain text response to a channel, for when embeds can't be used
+pub async fn send_text(
     ctx: &Context,
-) {
-    let guild = message.guild(&ctx).await.unwrap();
-    let breaker = message
-        .member
-        .clone()
-        .unwrap()
-        .nick
-        .unwrap_or(message.author.name.clone());
+    message: &Message,
+    summary: &ChainSummary,
+    channel: ChannelId,
+    ping: bool,
+) -> serenity::Result<()> {
+    channel
+        .send_message(&ctx, |m| {
+            m.content(format!(
+                "{}{}{}",
+                t!(
+                    summary.locale,
+                    "style.text",
+                    length = summary.length,
+                    starter = summary.starter,
+                    breaker = summary.breaker,
+                    points = summary.points_list()
+                ),
+                jump_link(message, channel, summary.locale),
+                ping_line(summary, ping)
+            ));
+            set_allowed_mentions(m, summary, ping);
+            m
+        })
+        .await?;
+
+    Ok(())
+}
 
-    let mut members = Vec::new();
+struct TextStyle;
 
-    for m_id in &chain.chainers {
-        members.push(guild.member(&ctx, m_id));
+#[async_trait]
+impl ResponseStyle for TextStyle {
+    fn name(&self) -> &'static str {
+        "text"
     }
 
-    let mut members = join_all(members)
-        .await
-        .into_iter()
-        .filter_map(|m| m.map(|m| Some(m)).unwrap_or(None))
-        .collect::<Vec<_>>();
+    async fn send(
+        &self,
+        ctx: &Context,
+        message: &Message,
+        summary: &ChainSummary,
+        settings: &GuildSettings,
+        channel: ChannelId,
+    ) -> serenity::Result<()> {
+        send_text(ctx, message, summary, channel, settings.ping_participants).await
+    }
+}
+
+struct ClassicStyle;
 
-    if !chain.chainers.contains(&message.author.id) {
-        members.push(message.member(&ctx).await.unwrap());
+#[async_trait]
+impl ResponseStyle for ClassicStyle {
+    fn name(&self) -> &'static str {
+        "classic"
     }
 
-    message
-        .channel_id
-        .send_message(&ctx, |m| {
-            m.content(format!(
-                "{} chain!\nStarter: {}\nBreaker: {}\nPoints:\n{}",
-                chain.length,
-                chain.starter.display_name(),
-                &breaker,
-                points.iter().fold(String::new(), |mut str, (id, i)| {
-                    let member = members.iter().filter(|m| &m.user.id == id).next().unwrap();
-                    str.push_str(&format!("{}: {}", member.display_name(), i));
-                    str
-                })
-            ));
-            m
-        })
-        .await
-        .unwrap();
+    async fn send(
+        &self,
+        ctx: &Context,
+        message: &Message,
+        summary: &ChainSummary,
+        settings: &GuildSettings,
+        channel: ChannelId,
+    ) -> serenity::Result<()> {
+        let ping = settings.ping_participants;
+
+        channel
+            .send_message(&ctx, |m| {
+                m.content(format!(
+                    "{}{}{}",
+                    t!(summary.locale, "style.classic", length = summary.length),
+                    jump_link(message, channel, summary.locale),
+                    ping_line(summary, ping)
+                ));
+                set_allowed_mentions(m, summary, ping);
+                m
+            })
+            .await?;
+
+        Ok(())
+    }
 }
 
-pub async fn classic_style(chain: &Chain, message: &Message, ctx: &Context) {
-    message
-        .channel_id
-        .send_message(&ctx, |m| {
-            m.content(format!(
-                "That was a {} chain! <:booby:633112900382359555>",
-                chain.length
-            ));
-            m
-        })
-        .await
-        .unwrap();
+/// Sends the guild's own response template with its placeholders filled in
+struct TemplateStyle;
+
+#[async_trait]
+impl ResponseStyle for TemplateStyle {
+    fn name(&self) -> &'static str {
+        "template"
+    }
+
+    async fn send(
+        &self,
+        ctx: &Context,
+        message: &Message,
+        summary: &ChainSummary,
+        settings: &GuildSettings,
+        channel: ChannelId,
+    ) -> serenity::Result<()> {
+        let template = match &settings.response_template {
+            Some(t) => t,
+            // Guilds can pick the template style before setting a template
+            None => return TextStyle.send(ctx, message, summary, settings, channel).await,
+        };
+
+        let mut values = HashMap::new();
+        values.insert("length", summary.length.to_string());
+        values.insert("content", summary.content.clone());
+        values.insert("starter", summary.starter.clone());
+        values.insert("breaker", summary.breaker.clone());
+        values.insert("participants", summary.participants.join(", "));
+        values.insert("points", summary.points_list());
+
+        // Make room for the jump link and pings in case the template fills the whole message
+        let ping = settings.ping_participants;
+        let suffix = jump_link(message, channel, summary.locale) + &ping_line(summary, ping);
+        let content = render_template(template, &values)
+            .chars()
+            .take(MAX_MESSAGE_LENGTH.saturating_sub(suffix.chars().count()))
+            .chain(suffix.chars())
+            .collect::<String>();
+
+        channel
+            .send_message(&ctx, |m| {
+                m.content(content);
+                set_allowed_mentions(m, summary, ping);
+                m
+            })
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Attaches a rendered card with the chain's text and everyone's points
+struct ImageStyle;
+
+#[async_trait]
+impl ResponseStyle for ImageStyle {
+    fn name(&self) -> &'static str {
+        "image"
+    }
+
+    fn required_permissions(&self) -> Permissions {
+        Permissions::ATTACH_FILES
+    }
+
+    async fn send(
+        &self,
+        ctx: &Context,
+        message: &Message,
+        summary: &ChainSummary,
+        settings: &GuildSettings,
+        channel: ChannelId,
+    ) -> serenity::Result<()> {
+        let card = match render_card(summary) {
+            Some(c) => c,
+            None => return TextStyle.send(ctx, message, summary, settings, channel).await,
+        };
+
+        let ping = settings.ping_participants;
+
+        channel
+            .send_message(&ctx, |m| {
+                m.content(format!(
+                    "{}{}{}",
+                    t!(summary.locale, "style.classic", length = summary.length),
+                    jump_link(message, channel, summary.locale),
+                    ping_line(summary, ping)
+                ));
+                set_allowed_mentions(m, summary, ping);
+                m.add_file(AttachmentType::Bytes {
+                    data: Cow::Owned(card),
+                    filename: "chain.png".to_owned(),
+                });
+                m
+            })
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Reacts to the breaking message instead of sending a response
+struct ReactStyle;
+
+/// Used when the chain length can't be spelled out, reactions can't repeat an emoji
+const DEFAULT_REACTION: &str = "🔗";
+
+const DIGIT_EMOJI: [&str; 10] = [
+    "0\u{fe0f}\u{20e3}",
+    "1\u{fe0f}\u{20e3}",
+    "2\u{fe0f}\u{20e3}",
+    "3\u{fe0f}\u{20e3}",
+    "4\u{fe0f}\u{20e3}",
+    "5\u{fe0f}\u{20e3}",
+    "6\u{fe0f}\u{20e3}",
+    "7\u{fe0f}\u{20e3}",
+    "8\u{fe0f}\u{20e3}",
+    "9\u{fe0f}\u{20e3}",
+];
+
+/// Spells out a chain length with keycap emoji
+fn length_emoji(length: u16) -> Vec<&'static str> {
+    if length == 10 {
+        return vec!["🔟"];
+    }
+
+    let mut emoji = Vec::new();
+    for digit in length.to_string().chars() {
+        let e = DIGIT_EMOJI[digit.to_digit(10).unwrap() as usize];
+        if emoji.contains(&e) {
+            return vec![DEFAULT_REACTION];
+        }
+        emoji.push(e);
+    }
+
+    emoji
+}
+
+#[async_trait]
+impl ResponseStyle for ReactStyle {
+    fn name(&self) -> &'static str {
+        "react"
+    }
+
+    fn required_permissions(&self) -> Permissions {
+        Permissions::ADD_REACTIONS | Permissions::READ_MESSAGE_HISTORY
+    }
+
+    fn posts_message(&self) -> bool {
+        false
+    }
+
+    async fn send(
+        &self,
+        ctx: &Context,
+        message: &Message,
+        summary: &ChainSummary,
+        settings: &GuildSettings,
+        _channel: ChannelId,
+    ) -> serenity::Result<()> {
+        let reactions = match &settings.react_emoji {
+            Some(emoji) => vec![emoji.as_str()],
+            None => length_emoji(summary.length),
+        };
+
+        // Added one at a time so they show up in order
+        for reaction in reactions {
+            let reaction = reaction
+                .parse::<ReactionType>()
+                .map_err(|_| serenity::Error::Other("Invalid react emoji"))?;
+            message.react(&ctx, reaction).await?;
+        }
+
+        Ok(())
+    }
 }
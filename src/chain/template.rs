@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+
+/// The placeholders that can be used in a response template
+pub const TEMPLATE_PLACEHOLDERS: [&str; 6] = [
+    "length",
+    "content",
+    "starter",
+    "breaker",
+    "participants",
+    "points",
+];
+
+/// The longest template we accept, leaving room in the message for the filled in placeholders
+pub const MAX_TEMPLATE_LENGTH: usize = 1000;
+
+/// Messages can be at most 2000 characters
+pub const MAX_MESSAGE_LENGTH: usize = 2000;
+
+enum Part<'a> {
+    Text(String),
+    Placeholder(&'a str),
+}
+
+/// Splits a template into text and placeholders
+///
+/// Placeholders are written as `{name}`, `{{` and `}}` are literal braces
+fn parse(template: &str) -> Result<Vec<Part<'_>>, String> {
+    let mut parts = Vec::new();
+    let mut text = String::new();
+    let mut chars = template.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '{' if chars.peek().map(|(_, c)| *c) == Some('{') => {
+                chars.next();
+                text.push('{');
+            }
+            '}' if chars.peek().map(|(_, c)| *c) == Some('}') => {
+                chars.next();
+                text.push('}');
+            }
+            '{' => {
+                let end = match template[i ..].find('}') {
+                    Some(end) => i + end,
+                    None => return Err("a placeholder is missing its closing }".to_owned()),
+                };
+                let name = &template[i + 1 .. end];
+
+                if !TEMPLATE_PLACEHOLDERS.contains(&name) {
+                    return Err(format!(
+                        "{{{}}} isn't a placeholder, use one of {}",
+                        name,
+                        TEMPLATE_PLACEHOLDERS
+                            .iter()
+                            .map(|p| format!("{{{}}}", p))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ));
+                }
+
+                if !text.is_empty() {
+                    parts.push(Part::Text(std::mem::take(&mut text)));
+                }
+                parts.push(Part::Placeholder(name));
+
+                for (j, _) in chars.by_ref() {
+                    if j == end {
+                        break;
+                    }
+                }
+            }
+            '}' => return Err("there is a } without a placeholder, use }} for a brace".to_owned()),
+            _ => text.push(c),
+        }
+    }
+
+    if !text.is_empty() {
+        parts.push(Part::Text(text));
+    }
+
+    Ok(parts)
+}
+
+/// Checks that a template only uses known placeholders and isn't too long
+pub fn validate_template(template: &str) -> Result<(), String> {
+    if template.trim().is_empty() {
+        return Err("the template is empty".to_owned());
+    }
+
+    if template.chars().count() > MAX_TEMPLATE_LENGTH {
+        return Err(format!(
+            "the template is longer than {} characters",
+            MAX_TEMPLATE_LENGTH
+        ));
+    }
+
+    parse(template).map(|_| ())
+}
+
+/// Fills in a template's placeholders, cutting it down to fit in a message
+///
+/// Placeholders missing from `values` are left empty
+pub fn render_template(template: &str, values: &HashMap<&str, String>) -> String {
+    let rendered = match parse(template) {
+        Ok(parts) => parts
+            .into_iter()
+            .map(|p| match p {
+                Part::Text(t) => t,
+                Part::Placeholder(name) => values.get(name).cloned().unwrap_or_default(),
+            })
+            .collect::<String>(),
+        // Templates are validated when set, so this only happens if the database was edited
+        Err(_) => template.to_owned(),
+    };
+
+    rendered.chars().take(MAX_MESSAGE_LENGTH).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values() -> HashMap<&'static str, String> {
+        let mut values = HashMap::new();
+        values.insert("length", "5".to_owned());
+        values.insert("content", "{breaker}".to_owned());
+        values.insert("breaker", "someone".to_owned());
+        values
+    }
+
+    #[test]
+    fn doubled_braces_are_literal() {
+        assert!(validate_template("{{length}} is {length}}}").is_ok());
+        assert_eq!(render_template("{{length}} is {length}}}", &values()), "{length} is 5}");
+    }
+
+    #[test]
+    fn values_are_not_parsed() {
+        assert_eq!(render_template("{content}", &values()), "{breaker}");
+    }
+
+    #[test]
+    fn missing_values_are_empty() {
+        assert_eq!(render_template("[{points}]", &values()), "[]");
+    }
+
+    #[test]
+    fn unknown_placeholders_are_rejected() {
+        assert!(validate_template("{nope}").is_err());
+        assert!(validate_template("{}").is_err());
+    }
+
+    #[test]
+    fn unclosed_placeholders_are_rejected() {
+        assert!(validate_template("{length").is_err());
+        assert!(validate_template("length}").is_err());
+    }
+
+    #[test]
+    fn empty_and_long_templates_are_rejected() {
+        assert!(validate_template("   ").is_err());
+        assert!(validate_template(&"a".repeat(MAX_TEMPLATE_LENGTH)).is_ok());
+        assert!(validate_template(&"a".repeat(MAX_TEMPLATE_LENGTH + 1)).is_err());
+    }
+
+    #[test]
+    fn output_fits_in_a_message() {
+        let mut values = values();
+        values.insert("content", "é".repeat(MAX_MESSAGE_LENGTH));
+
+        let rendered = render_template("{length} {content}", &values);
+        assert_eq!(rendered.chars().count(), MAX_MESSAGE_LENGTH);
+        assert!(rendered.starts_with("5 é"));
+    }
+}
@@ -4,7 +4,7 @@ use diesel::{
     backend::Backend,
     deserialize::Queryable,
     serialize::Output,
-    sql_types::BigInt,
+    sql_types::{BigInt, Text},
     types::{FromSql, ToSql},
     Connection,
     PgConnection,
@@ -12,6 +12,8 @@ use diesel::{
 
 pub use super::tables::{guilds, users::*};
 
+use crate::bot::guild_settings::{MatchMode, StyleName};
+
 pub fn establish_connection() -> PgConnection {
     let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL not set");
     PgConnection::establish(&database_url).expect(&format!("Error connecting to {}", &database_url))
@@ -58,3 +60,26 @@ where i64: Queryable<BigInt, DB>
         (i64::build(row) as u64).into()
     }
 }
+
+/// Maps settings types to their names in Text columns
+macro_rules! text_enum_mapping {
+    ($($type: ty),*) => {
+        $(impl<DB: Backend> FromSql<Text, DB> for $type
+        where String: FromSql<Text, DB>
+        {
+            fn from_sql(bytes: Option<&DB::RawValue>) -> diesel::deserialize::Result<Self> {
+                Ok(String::from_sql(bytes)?.parse()?)
+            }
+        }
+
+        impl<DB: Backend> ToSql<Text, DB> for $type
+        where str: ToSql<Text, DB>
+        {
+            fn to_sql<W: Write>(&self, out: &mut Output<W, DB>) -> diesel::serialize::Result {
+                self.name().to_sql(out)
+            }
+        })*
+    };
+}
+
+text_enum_mapping!(StyleName, MatchMode);
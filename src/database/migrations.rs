@@ -0,0 +1,43 @@
+use diesel::{dsl::sql, prelude::*, sql_types::Bool, PgConnection};
+use diesel_migrations::{MigrationConnection, RunMigrationsError};
+
+// Embed everything in /migrations so deployments don't need the diesel cli
+//
+// This is what `embed_migrations!` expands to, with a way to list the embedded versions added
+#[allow(dead_code)]
+mod embedded_migrations {
+    #[derive(EmbedMigrations)]
+    struct _Dummy;
+
+    /// The versions of every embedded migration, oldest first
+    pub fn versions() -> impl Iterator<Item = &'static str> {
+        ALL_MIGRATIONS.iter().map(|m| m.version())
+    }
+}
+
+/// Runs any embedded migrations that have not been applied to the database yet
+pub fn run_migrations(conn: &PgConnection) -> Result<(), RunMigrationsError> {
+    embedded_migrations::run_with_output(conn, &mut std::io::stdout())
+}
+
+/// Gets the versions of the embedded migrations that have not been applied to the database yet
+///
+/// This only reads from the database, so it's safe to run against production
+pub fn pending_migrations(conn: &PgConnection) -> Result<Vec<String>, RunMigrationsError> {
+    // Diesel makes its migrations table when the first migration runs
+    let has_table = diesel::select(sql::<Bool>(
+        "to_regclass('__diesel_schema_migrations') IS NOT NULL",
+    ))
+    .get_result::<bool>(conn)?;
+
+    let applied = if has_table {
+        conn.previously_run_migration_versions()?
+    } else {
+        Default::default()
+    };
+
+    Ok(embedded_migrations::versions()
+        .filter(|v| !applied.contains(*v))
+        .map(|v| v.to_owned())
+        .collect())
+}
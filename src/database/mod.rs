@@ -1,5 +1,7 @@
 #![allow(dead_code)]
 mod database;
+mod migrations;
 pub mod schema;
 pub use database::*;
+pub use migrations::*;
 pub mod tables;
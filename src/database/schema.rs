@@ -1,3 +1,37 @@
+table! {
+    chain_history (id) {
+        id -> Int4,
+        guild_id -> Int8,
+        channel_id -> Int8,
+        message -> Text,
+        length -> Int4,
+        starter_id -> Nullable<Int8>,
+        breaker_id -> Nullable<Int8>,
+        ended_at -> Timestamp,
+    }
+}
+
+table! {
+    chain_participants (chain_id, user_id) {
+        chain_id -> Int4,
+        user_id -> Int8,
+        messages -> Int4,
+        points -> Int8,
+    }
+}
+
+table! {
+    channel_settings (channel_id) {
+        channel_id -> Int8,
+        guild_id -> Int8,
+        chain_threshold -> Nullable<Int2>,
+        style -> Nullable<Text>,
+        remove_messages -> Nullable<Bool>,
+        alternate_member -> Nullable<Bool>,
+        match_mode -> Nullable<Text>,
+    }
+}
+
 table! {
     guilds (id) {
         id -> Int8,
@@ -8,6 +42,19 @@ table! {
         remove_messages -> Bool,
         chain_threshold -> Int2,
         alternate_member -> Bool,
+        active -> Bool,
+        left_at -> Nullable<Timestamp>,
+        match_mode -> Text,
+        manager_roles -> Array<Int8>,
+        log_channel -> Nullable<Int8>,
+        response_template -> Nullable<Text>,
+        react_emoji -> Nullable<Text>,
+        log_responses -> Bool,
+        announce_channel -> Nullable<Int8>,
+        milestones -> Array<Int2>,
+        locale -> Nullable<Text>,
+        ping_participants -> Bool,
+        replay_chains -> Bool,
     }
 }
 
@@ -20,6 +67,24 @@ table! {
     }
 }
 
+table! {
+    settings_audit (id) {
+        id -> Int4,
+        guild_id -> Int8,
+        actor_id -> Int8,
+        field -> Text,
+        old_value -> Text,
+        new_value -> Text,
+        changed_at -> Timestamp,
+    }
+}
+
+table! {
+    user_opt_outs (id) {
+        id -> Int8,
+    }
+}
+
 table! {
     users (id) {
         id -> Int8,
@@ -28,4 +93,15 @@ table! {
     }
 }
 
-allow_tables_to_appear_in_same_query!(guilds, server_users, users,);
+joinable!(chain_participants -> chain_history (chain_id));
+
+allow_tables_to_appear_in_same_query!(
+    chain_history,
+    chain_participants,
+    channel_settings,
+    guilds,
+    server_users,
+    settings_audit,
+    user_opt_outs,
+    users,
+);
@@ -0,0 +1,80 @@
+use std::time::SystemTime;
+
+use diesel::{pg::PgConnection, prelude::*, Queryable};
+use serenity::model::id::{GuildId, UserId};
+
+use crate::database::{schema::*, U64Wrapper};
+
+pub fn record_setting_change(
+    conn: &PgConnection,
+    guild: GuildId,
+    actor: UserId,
+    changed_field: &str,
+    old: &str,
+    new: &str,
+) {
+    use self::settings_audit::dsl::*;
+
+    diesel::insert_into(settings_audit)
+        .values((
+            guild_id.eq::<U64Wrapper>(guild.0.into()),
+            actor_id.eq::<U64Wrapper>(actor.0.into()),
+            field.eq(changed_field),
+            old_value.eq(old),
+            new_value.eq(new),
+        ))
+        .execute(conn)
+        .unwrap();
+}
+
+/// Gets a guild's settings changes, newest first
+pub fn get_setting_changes(
+    conn: &PgConnection,
+    guild: GuildId,
+    offset: i64,
+    limit: i64,
+) -> Vec<SettingChangeRow> {
+    use self::settings_audit::dsl::*;
+
+    settings_audit
+        .filter(guild_id.eq::<U64Wrapper>(guild.0.into()))
+        .order(changed_at.desc())
+        .offset(offset)
+        .limit(limit)
+        .load::<SettingChangeRow>(conn)
+        .unwrap()
+}
+
+/// Gets every settings change a user made, in any guild, oldest first
+pub fn get_user_setting_changes(conn: &PgConnection, user: UserId) -> Vec<SettingChangeRow> {
+    use self::settings_audit::dsl::*;
+
+    settings_audit
+        .filter(actor_id.eq::<U64Wrapper>(user.0.into()))
+        .order(changed_at.asc())
+        .load::<SettingChangeRow>(conn)
+        .unwrap()
+}
+
+/// Removes a user from the settings changes they made
+///
+/// The changes themselves stay so the guild's history still adds up, with an actor of 0
+pub fn anonymize_setting_changes(conn: &PgConnection, user: UserId) {
+    use self::settings_audit::dsl::*;
+
+    diesel::update(settings_audit.filter(actor_id.eq::<U64Wrapper>(user.0.into())))
+        .set(actor_id.eq(U64Wrapper(0)))
+        .execute(conn)
+        .unwrap();
+}
+
+#[derive(Queryable, Clone)]
+pub struct SettingChangeRow {
+    pub id: i32,
+    pub guild_id: U64Wrapper,
+    pub actor_id: U64Wrapper,
+    pub field: String,
+    pub old_value: String,
+    pub new_value: String,
+    pub changed_at: SystemTime,
+}
@@ -1,97 +1,304 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime},
+};
 
 use diesel::{pg::PgConnection, prelude::*, Queryable};
-use serenity::model::id::{ChannelId, GuildId};
+use serenity::model::id::{ChannelId, GuildId, RoleId};
 
 use crate::{
-    bot::guild_settings::GuildSettings,
+    bot::guild_settings::{
+        parse_threshold,
+        validate_prefixes,
+        validate_locale,
+        validate_milestones,
+        validate_react_emoji,
+        ChannelSettings,
+        GuildSettings,
+        MatchMode,
+        StyleName,
+        DEFAULT_SETTINGS,
+    },
+    chain::template::validate_template,
     database::{schema::*, U64Wrapper},
 };
 
 pub fn update_guild(conn: &PgConnection, guild_id: GuildId, settings: &GuildSettings) {
     use self::guilds::dsl::*;
 
-    let row = GuildRow {
-        id: guild_id.0.into(),
-        prefixes: settings.prefixes.clone(),
-        channel_filters: settings
-            .channel_filters
-            .iter()
-            .map(|v| v.0.into())
-            .collect(),
-        blacklist: settings.blacklist,
-        style: settings.style.clone(),
-        remove_messages: settings.remove_messages,
-        chain_threshold: settings.chain_threshold as i16,
-        alternate_member: settings.alternate_member,
-    };
-
     diesel::update(guilds.filter(id.eq::<U64Wrapper>(guild_id.0.into())))
         .set((
-            prefixes.eq(row.prefixes),
-            channel_filters.eq(row.channel_filters),
-            blacklist.eq(row.blacklist),
-            style.eq(row.style),
-            remove_messages.eq(row.remove_messages),
-            chain_threshold.eq(row.chain_threshold),
-            alternate_member.eq(row.alternate_member),
+            prefixes.eq(settings.prefixes.clone()),
+            channel_filters.eq(settings
+                .channel_filters
+                .iter()
+                .map(|v| v.0.into())
+                .collect::<Vec<U64Wrapper>>()),
+            blacklist.eq(settings.blacklist),
+            style.eq(settings.style),
+            remove_messages.eq(settings.remove_messages),
+            chain_threshold.eq(settings.chain_threshold as i16),
+            alternate_member.eq(settings.alternate_member),
+            match_mode.eq(settings.match_mode),
+            manager_roles.eq(settings
+                .manager_roles
+                .iter()
+                .map(|v| v.0.into())
+                .collect::<Vec<U64Wrapper>>()),
+            log_channel.eq(settings.log_channel.map(|c| U64Wrapper(c.0))),
+            response_template.eq(&settings.response_template),
+            react_emoji.eq(&settings.react_emoji),
+            log_responses.eq(settings.log_responses),
+            announce_channel.eq(settings.announce_channel.map(|c| U64Wrapper(c.0))),
+            milestones.eq(settings.milestones.iter().map(|m| *m as i16).collect::<Vec<_>>()),
+            locale.eq(&settings.locale),
+            ping_participants.eq(settings.ping_participants),
+            replay_chains.eq(settings.replay_chains),
         ))
         .execute(conn)
         .unwrap();
+
+    update_channel_settings(conn, guild_id, &settings.channel_overrides);
+}
+
+/// Replaces all of a guild's channel overrides
+pub fn update_channel_settings(
+    conn: &PgConnection,
+    guild_id: GuildId,
+    overrides: &HashMap<ChannelId, ChannelSettings>,
+) {
+    let rows = overrides
+        .iter()
+        .filter(|(_, o)| !o.is_empty())
+        .map(|(channel, o)| ChannelSettingsRow {
+            channel_id: channel.0.into(),
+            guild_id: guild_id.0.into(),
+            chain_threshold: o.chain_threshold.map(|t| t as i16),
+            style: o.style,
+            remove_messages: o.remove_messages,
+            alternate_member: o.alternate_member,
+            match_mode: o.match_mode,
+        })
+        .collect::<Vec<_>>();
+
+    let filter = channel_settings::table
+        .filter(channel_settings::guild_id.eq::<U64Wrapper>(guild_id.0.into()));
+
+    conn.transaction::<_, diesel::result::Error, _>(|| {
+        diesel::delete(filter).execute(conn)?;
+        diesel::insert_into(channel_settings::table)
+            .values(&rows)
+            .execute(conn)?;
+        Ok(())
+    })
+    .unwrap();
 }
 
 
+/// Adds a guild with the default settings
+///
+/// Every column is written explicitly as the defaults can be changed by the bot operator
 pub fn new_guild(conn: &PgConnection, guild_id: GuildId) -> GuildSettings {
     use self::guilds::dsl::*;
     let result: GuildRow = diesel::insert_into(guilds)
-        .values(id.eq::<U64Wrapper>(guild_id.0.into()))
+        .values(&row_from_settings(guild_id, &DEFAULT_SETTINGS))
         .get_result(conn)
         .unwrap();
 
-    GuildSettings {
-        prefixes: result.prefixes,
-        channel_filters: result
-            .channel_filters
-            .iter()
-            .map(|v| (*v).into())
-            .collect::<Vec<u64>>()
-            .iter()
-            .map(|v| ChannelId(*v))
-            .collect(),
-        blacklist: result.blacklist,
-        style: result.style,
-        remove_messages: result.remove_messages,
-        chain_threshold: result.chain_threshold as u16,
-        alternate_member: result.alternate_member,
-    }
+    settings_from_row(&result, HashMap::new())
+}
+
+/// Loads a single guild's settings, if it has any stored
+pub fn get_guild(conn: &PgConnection, guild_id: GuildId) -> Option<GuildSettings> {
+    use self::guilds::dsl::*;
+
+    let row = guilds
+        .filter(id.eq::<U64Wrapper>(guild_id.0.into()))
+        .first::<GuildRow>(conn)
+        .optional()
+        .unwrap()?;
+    let overrides = get_channel_settings(conn, Some(guild_id))
+        .remove(&guild_id)
+        .unwrap_or_default();
+
+    Some(settings_from_row(&row, overrides))
 }
 
 pub fn get_guilds(conn: &PgConnection) -> HashMap<GuildId, GuildSettings> {
     use self::guilds::dsl::*;
 
     let results = guilds.load::<GuildRow>(conn).unwrap();
+    let mut overrides = get_channel_settings(conn, None);
 
     results
         .iter()
         .map(|row| {
-            (GuildId(row.id.into()), GuildSettings {
-                prefixes: row.prefixes.clone(),
-                channel_filters: row
-                    .channel_filters
-                    .iter()
-                    .map(|v| (*v).into())
-                    .map(|v: u64| ChannelId(v))
-                    .collect(),
-                blacklist: row.blacklist,
-                style: row.style.clone(),
-                remove_messages: row.remove_messages,
-                chain_threshold: row.chain_threshold as u16,
-                alternate_member: row.alternate_member,
-            })
+            let guild_id = GuildId(row.id.into());
+            let guild_overrides = overrides.remove(&guild_id).unwrap_or_default();
+            (guild_id, settings_from_row(row, guild_overrides))
         })
         .collect()
 }
 
+/// Gets the channel overrides of every guild
+fn get_channel_settings(
+    conn: &PgConnection,
+    guild: Option<GuildId>,
+) -> HashMap<GuildId, HashMap<ChannelId, ChannelSettings>> {
+    use self::channel_settings::dsl::*;
+
+    let mut overrides: HashMap<GuildId, HashMap<ChannelId, ChannelSettings>> = HashMap::new();
+
+    let mut query = channel_settings.into_boxed();
+    if let Some(g) = guild {
+        query = query.filter(guild_id.eq::<U64Wrapper>(g.0.into()));
+    }
+
+    for row in query.load::<ChannelSettingsRow>(conn).unwrap() {
+        let guild: u64 = row.guild_id.into();
+        let channel: u64 = row.channel_id.into();
+
+        let threshold = match row.chain_threshold.map(|t| parse_threshold(t as i64)) {
+            Some(Ok(t)) => Some(t),
+            Some(Err(e)) => {
+                println!("Channel {} has an invalid chain threshold: {}", channel, e);
+                None
+            }
+            None => None,
+        };
+
+        overrides
+            .entry(GuildId(guild))
+            .or_default()
+            .insert(ChannelId(channel), ChannelSettings {
+                chain_threshold: threshold,
+                style: row.style,
+                remove_messages: row.remove_messages,
+                alternate_member: row.alternate_member,
+                match_mode: row.match_mode,
+            });
+    }
+
+    overrides
+}
+
+/// Converts a row into settings, running it through the same validation as the settings commands
+///
+/// Invalid values can only come from editing the database by hand,
+/// so we log them and fall back to the defaults instead of refusing to start
+fn settings_from_row(
+    row: &GuildRow,
+    channel_overrides: HashMap<ChannelId, ChannelSettings>,
+) -> GuildSettings {
+    let guild_id: u64 = row.id.into();
+
+    GuildSettings {
+        prefixes: match validate_prefixes(&row.prefixes) {
+            Ok(_) => row.prefixes.clone(),
+            Err(e) => {
+                println!("Guild {} has invalid prefixes: {}", guild_id, e);
+                DEFAULT_SETTINGS.prefixes.clone()
+            }
+        },
+        channel_filters: row
+            .channel_filters
+            .iter()
+            .map(|v| (*v).into())
+            .map(|v: u64| ChannelId(v))
+            .collect(),
+        blacklist: row.blacklist,
+        style: row.style,
+        remove_messages: row.remove_messages,
+        chain_threshold: match parse_threshold(row.chain_threshold as i64) {
+            Ok(threshold) => threshold,
+            Err(e) => {
+                println!("Guild {} has an invalid chain threshold: {}", guild_id, e);
+                DEFAULT_SETTINGS.chain_threshold
+            }
+        },
+        alternate_member: row.alternate_member,
+        match_mode: row.match_mode,
+        channel_overrides,
+        manager_roles: row
+            .manager_roles
+            .iter()
+            .map(|v| (*v).into())
+            .map(|v: u64| RoleId(v))
+            .collect(),
+        log_channel: row.log_channel.map(|c| ChannelId(c.into())),
+        response_template: match &row.response_template {
+            Some(template) => match validate_template(template) {
+                Ok(_) => Some(template.clone()),
+                Err(e) => {
+                    println!("Guild {} has an invalid response template: {}", guild_id, e);
+                    None
+                }
+            },
+            None => None,
+        },
+        react_emoji: match &row.react_emoji {
+            Some(emoji) => match validate_react_emoji(emoji) {
+                Ok(_) => Some(emoji.clone()),
+                Err(e) => {
+                    println!("Guild {} has an invalid react emoji: {}", guild_id, e);
+                    None
+                }
+            },
+            None => None,
+        },
+        log_responses: row.log_responses,
+        announce_channel: row.announce_channel.map(|c| ChannelId(c.into())),
+        milestones: {
+            let milestones = row.milestones.iter().map(|m| *m as u16).collect::<Vec<_>>();
+            match validate_milestones(&milestones) {
+                Ok(_) => milestones,
+                Err(e) => {
+                    println!("Guild {} has invalid milestones: {}", guild_id, e);
+                    DEFAULT_SETTINGS.milestones.clone()
+                }
+            }
+        },
+        locale: match &row.locale {
+            Some(locale) => match validate_locale(locale) {
+                Ok(_) => Some(locale.clone()),
+                Err(e) => {
+                    println!("Guild {} has an unknown locale: {}", guild_id, e);
+                    None
+                }
+            },
+            None => None,
+        },
+        ping_participants: row.ping_participants,
+        replay_chains: row.replay_chains,
+    }
+}
+
+fn row_from_settings(guild_id: GuildId, settings: &GuildSettings) -> GuildRow {
+    GuildRow {
+        id: guild_id.0.into(),
+        prefixes: settings.prefixes.clone(),
+        channel_filters: settings.channel_filters.iter().map(|v| v.0.into()).collect(),
+        blacklist: settings.blacklist,
+        style: settings.style,
+        remove_messages: settings.remove_messages,
+        chain_threshold: settings.chain_threshold as i16,
+        alternate_member: settings.alternate_member,
+        active: true,
+        left_at: None,
+        match_mode: settings.match_mode,
+        manager_roles: settings.manager_roles.iter().map(|v| v.0.into()).collect(),
+        log_channel: settings.log_channel.map(|c| U64Wrapper(c.0)),
+        response_template: settings.response_template.clone(),
+        react_emoji: settings.react_emoji.clone(),
+        log_responses: settings.log_responses,
+        announce_channel: settings.announce_channel.map(|c| U64Wrapper(c.0)),
+        milestones: settings.milestones.iter().map(|m| *m as i16).collect(),
+        locale: settings.locale.clone(),
+        ping_participants: settings.ping_participants,
+        replay_chains: settings.replay_chains,
+    }
+}
+
 #[derive(Insertable, Queryable, Debug)]
 #[table_name = "guilds"]
 struct GuildRow {
@@ -99,10 +306,100 @@ struct GuildRow {
     pub prefixes: Vec<String>,
     pub channel_filters: Vec<U64Wrapper>,
     pub blacklist: bool,
-    pub style: String,
+    // Styles are checked against the style registry when they're loaded
+    pub style: StyleName,
     pub remove_messages: bool,
     pub chain_threshold: i16,
     pub alternate_member: bool,
+    pub active: bool,
+    pub left_at: Option<SystemTime>,
+    pub match_mode: MatchMode,
+    pub manager_roles: Vec<U64Wrapper>,
+    pub log_channel: Option<U64Wrapper>,
+    pub response_template: Option<String>,
+    pub react_emoji: Option<String>,
+    pub log_responses: bool,
+    pub announce_channel: Option<U64Wrapper>,
+    pub milestones: Vec<i16>,
+    pub locale: Option<String>,
+    pub ping_participants: bool,
+    pub replay_chains: bool,
+}
+
+#[derive(Insertable, Queryable, Debug)]
+#[table_name = "channel_settings"]
+struct ChannelSettingsRow {
+    pub channel_id: U64Wrapper,
+    pub guild_id: U64Wrapper,
+    pub chain_threshold: Option<i16>,
+    pub style: Option<StyleName>,
+    pub remove_messages: Option<bool>,
+    pub alternate_member: Option<bool>,
+    pub match_mode: Option<MatchMode>,
+}
+
+/// Marks a guild as one we are currently in
+pub fn mark_guild_active(conn: &PgConnection, guild_id: GuildId) {
+    use self::guilds::dsl::*;
+
+    diesel::update(guilds.filter(id.eq::<U64Wrapper>(guild_id.0.into())))
+        .set((active.eq(true), left_at.eq::<Option<SystemTime>>(None)))
+        .execute(conn)
+        .unwrap();
+}
+
+/// Marks a guild as one we have left, starting its retention period
+pub fn mark_guild_inactive(conn: &PgConnection, guild_id: GuildId) {
+    use self::guilds::dsl::*;
+
+    diesel::update(guilds.filter(id.eq::<U64Wrapper>(guild_id.0.into())))
+        .set((active.eq(false), left_at.eq(SystemTime::now())))
+        .execute(conn)
+        .unwrap();
+}
+
+/// Deletes all data for guilds we left more than `retention` ago
+///
+/// Returns the ids of the guilds that were removed
+pub fn purge_inactive_guilds(conn: &PgConnection, retention: Duration) -> Vec<GuildId> {
+    use self::guilds::dsl::*;
+
+    let cutoff = SystemTime::now() - retention;
+
+    // Everything goes together so a failure can't leave rows for a guild that's gone
+    let removed = conn
+        .transaction::<_, diesel::result::Error, _>(|| {
+            let removed =
+                diesel::delete(guilds.filter(active.eq(false)).filter(left_at.lt(cutoff)))
+                    .returning(id)
+                    .get_results::<U64Wrapper>(conn)?;
+
+            if !removed.is_empty() {
+                diesel::delete(
+                    server_users::table.filter(server_users::server_id.eq_any(removed.clone())),
+                )
+                .execute(conn)?;
+                diesel::delete(
+                    channel_settings::table
+                        .filter(channel_settings::guild_id.eq_any(removed.clone())),
+                )
+                .execute(conn)?;
+                diesel::delete(
+                    settings_audit::table.filter(settings_audit::guild_id.eq_any(removed.clone())),
+                )
+                .execute(conn)?;
+                // Deleting the chains also deletes their participants
+                diesel::delete(
+                    chain_history::table.filter(chain_history::guild_id.eq_any(removed.clone())),
+                )
+                .execute(conn)?;
+            }
+
+            Ok(removed)
+        })
+        .unwrap();
+
+    removed.into_iter().map(|g| GuildId(g.into())).collect()
 }
 
 macro_rules! update_setting {
@@ -134,7 +431,7 @@ update_setting!(
     update_style,
     new_style,
     style,
-    String,
+    StyleName,
     update_remove,
     remove_flag,
     remove_messages,
@@ -0,0 +1,231 @@
+use std::{collections::HashMap, time::SystemTime};
+
+use diesel::{pg::PgConnection, prelude::*, Queryable};
+use serenity::model::id::{ChannelId, GuildId, UserId};
+
+use crate::{
+    chain::Chain,
+    database::{schema::*, U64Wrapper},
+};
+
+/// Stores a broken chain along with everyone who took part in it
+pub fn record_chain(
+    conn: &PgConnection,
+    guild: GuildId,
+    channel: ChannelId,
+    chain: &Chain,
+    breaker: UserId,
+    points: &HashMap<UserId, u64>,
+) {
+    conn.transaction::<_, diesel::result::Error, _>(|| {
+        let chain_id = diesel::insert_into(chain_history::table)
+            .values((
+                chain_history::guild_id.eq::<U64Wrapper>(guild.0.into()),
+                chain_history::channel_id.eq::<U64Wrapper>(channel.0.into()),
+                chain_history::message.eq(&chain.message),
+                chain_history::length.eq(chain.length as i32),
+                chain_history::starter_id.eq(Some(U64Wrapper(chain.starter.user.id.0))),
+                chain_history::breaker_id.eq(Some(U64Wrapper(breaker.0))),
+            ))
+            .returning(chain_history::id)
+            .get_result::<i32>(conn)?;
+
+        let participants = chain
+            .chainers
+            .iter()
+            .map(|u| ParticipantRow {
+                chain_id,
+                user_id: u.0.into(),
+                messages: *chain.num_messages.get(u).unwrap_or(&0) as i32,
+                points: *points.get(u).unwrap_or(&0) as i64,
+            })
+            .collect::<Vec<_>>();
+
+        diesel::insert_into(chain_participants::table)
+            .values(&participants)
+            .execute(conn)?;
+
+        Ok(())
+    })
+    .unwrap();
+}
+
+/// Gets the longest chain in a guild, or in one of its channels
+pub fn get_longest_chain(conn: &PgConnection, guild: GuildId, channel: Option<ChannelId>) -> i32 {
+    let mut query = chain_history::table
+        .filter(chain_history::guild_id.eq::<U64Wrapper>(guild.0.into()))
+        .select(diesel::dsl::max(chain_history::length))
+        .into_boxed();
+
+    if let Some(c) = channel {
+        query = query.filter(chain_history::channel_id.eq::<U64Wrapper>(c.0.into()));
+    }
+
+    query.first::<Option<i32>>(conn).unwrap().unwrap_or(0)
+}
+
+/// Counts what a user has done in a guild's chains
+pub fn get_chain_stats(conn: &PgConnection, guild: GuildId, user: UserId) -> ChainStats {
+    let joined = chain_participants::table
+        .inner_join(chain_history::table)
+        .filter(chain_participants::user_id.eq::<U64Wrapper>(user.0.into()))
+        .filter(chain_history::guild_id.eq::<U64Wrapper>(guild.0.into()))
+        .select((chain_history::channel_id, chain_history::length))
+        .load::<(U64Wrapper, i32)>(conn)
+        .unwrap();
+
+    let guild_chains =
+        chain_history::table.filter(chain_history::guild_id.eq::<U64Wrapper>(guild.0.into()));
+
+    let started = guild_chains
+        .clone()
+        .filter(chain_history::starter_id.eq(U64Wrapper(user.0)))
+        .count()
+        .get_result::<i64>(conn)
+        .unwrap();
+
+    let broken = guild_chains
+        .filter(chain_history::breaker_id.eq(U64Wrapper(user.0)))
+        .count()
+        .get_result::<i64>(conn)
+        .unwrap();
+
+    let mut channel_counts = HashMap::new();
+    for (channel, _) in &joined {
+        *channel_counts.entry(channel.0).or_insert(0) += 1;
+    }
+
+    ChainStats {
+        joined: joined.len() as i64,
+        started,
+        broken,
+        total_length: joined.iter().map(|(_, l)| *l as i64).sum(),
+        favourite_channel: channel_counts
+            .into_iter()
+            .max_by_key(|(_, count)| *count)
+            .map(|(c, _)| ChannelId(c)),
+    }
+}
+
+/// Counts the chains in a guild that both users took part in
+pub fn get_shared_chains(
+    conn: &PgConnection,
+    guild: GuildId,
+    user_a: UserId,
+    user_b: UserId,
+) -> i64 {
+    // Diesel can't use the same table in a subquery, so load the second user's chains first
+    let chains_b = chain_participants::table
+        .filter(chain_participants::user_id.eq::<U64Wrapper>(user_b.0.into()))
+        .select(chain_participants::chain_id)
+        .load::<i32>(conn)
+        .unwrap();
+
+    chain_participants::table
+        .inner_join(chain_history::table)
+        .filter(chain_participants::user_id.eq::<U64Wrapper>(user_a.0.into()))
+        .filter(chain_participants::chain_id.eq_any(chains_b))
+        .filter(chain_history::guild_id.eq::<U64Wrapper>(guild.0.into()))
+        .count()
+        .get_result::<i64>(conn)
+        .unwrap()
+}
+
+/// Counts how many times `breaker` broke a chain in a guild that `victim` was part of
+pub fn get_times_broken(
+    conn: &PgConnection,
+    guild: GuildId,
+    breaker: UserId,
+    victim: UserId,
+) -> i64 {
+    let victim_chains = chain_participants::table
+        .filter(chain_participants::user_id.eq::<U64Wrapper>(victim.0.into()))
+        .select(chain_participants::chain_id);
+
+    chain_history::table
+        .filter(chain_history::guild_id.eq::<U64Wrapper>(guild.0.into()))
+        .filter(chain_history::breaker_id.eq(U64Wrapper(breaker.0)))
+        .filter(chain_history::id.eq_any(victim_chains))
+        .count()
+        .get_result::<i64>(conn)
+        .unwrap()
+}
+
+/// Gets every chain a user took part in, for exporting their data
+pub fn get_user_participations(conn: &PgConnection, user: UserId) -> Vec<Participation> {
+    chain_participants::table
+        .inner_join(chain_history::table)
+        .filter(chain_participants::user_id.eq::<U64Wrapper>(user.0.into()))
+        .select((
+            chain_history::id,
+            chain_history::guild_id,
+            chain_history::channel_id,
+            chain_history::length,
+            chain_participants::messages,
+            chain_participants::points,
+            chain_history::ended_at,
+        ))
+        .order(chain_history::ended_at.desc())
+        .load::<Participation>(conn)
+        .unwrap()
+}
+
+/// Removes a user from the chain history
+///
+/// The chains themselves are kept as they include other users
+pub fn delete_user_history(conn: &PgConnection, user: UserId) {
+    use self::chain_history::dsl::*;
+
+    let filter = chain_participants::table
+        .filter(chain_participants::user_id.eq::<U64Wrapper>(user.0.into()));
+    diesel::delete(filter).execute(conn).unwrap();
+
+    diesel::update(chain_history.filter(starter_id.eq(U64Wrapper(user.0))))
+        .set(starter_id.eq::<Option<U64Wrapper>>(None))
+        .execute(conn)
+        .unwrap();
+
+    diesel::update(chain_history.filter(breaker_id.eq(U64Wrapper(user.0))))
+        .set(breaker_id.eq::<Option<U64Wrapper>>(None))
+        .execute(conn)
+        .unwrap();
+}
+
+/// What a user has done in a guild's chains
+pub struct ChainStats {
+    pub joined: i64,
+    pub started: i64,
+    pub broken: i64,
+    pub total_length: i64,
+    pub favourite_channel: Option<ChannelId>,
+}
+
+impl ChainStats {
+    pub fn average_length(&self) -> f64 {
+        if self.joined == 0 {
+            0.0
+        } else {
+            self.total_length as f64 / self.joined as f64
+        }
+    }
+}
+
+#[derive(Queryable, Clone)]
+pub struct Participation {
+    pub chain_id: i32,
+    pub guild_id: U64Wrapper,
+    pub channel_id: U64Wrapper,
+    pub length: i32,
+    pub messages: i32,
+    pub points: i64,
+    pub ended_at: SystemTime,
+}
+
+#[derive(Insertable)]
+#[table_name = "chain_participants"]
+struct ParticipantRow {
+    chain_id: i32,
+    user_id: U64Wrapper,
+    messages: i32,
+    points: i64,
+}
@@ -50,6 +50,56 @@ pub fn get_or_create_server_user(
     }
 }
 
+/// Gets a member's place on a server's points leaderboard, starting at 1
+pub fn get_server_rank(conn: &PgConnection, guild_id: GuildId, member_points: i64) -> i64 {
+    use self::server_users::dsl::*;
+
+    server_users
+        .filter(server_id.eq::<U64Wrapper>(guild_id.0.into()))
+        .filter(points.gt(member_points))
+        .count()
+        .get_result::<i64>(conn)
+        .unwrap()
+        + 1
+}
+
+/// Gets a user's place on the global points leaderboard, starting at 1
+pub fn get_global_rank(conn: &PgConnection, user_points: i64) -> i64 {
+    use self::users::dsl::*;
+
+    users
+        .filter(points.gt(user_points))
+        .count()
+        .get_result::<i64>(conn)
+        .unwrap()
+        + 1
+}
+
+/// Gets a user's data from every server they have chained in
+pub fn get_server_users(conn: &PgConnection, member_id: UserId) -> Vec<GuildUser> {
+    use self::server_users::dsl::*;
+
+    server_users
+        .filter(user_id.eq::<U64Wrapper>(member_id.0.into()))
+        .load::<GuildUser>(conn)
+        .unwrap()
+}
+
+/// Gets the longest chain anyone in a guild has been part of
+pub fn get_server_longest_chain(conn: &PgConnection, guild_id: GuildId) -> i32 {
+    use self::server_users::dsl::*;
+
+    server_users
+        .filter(server_id.eq::<U64Wrapper>(guild_id.0.into()))
+        .select(longest_chains)
+        .load::<Vec<i32>>(conn)
+        .unwrap()
+        .into_iter()
+        .flatten()
+        .max()
+        .unwrap_or(0)
+}
+
 #[derive(Queryable, Clone, Insertable)]
 #[table_name = "server_users"]
 pub struct GuildUser {
@@ -81,30 +131,35 @@ pub fn increase_server_points(
         .unwrap();
 }
 
+/// Adds a chain to a user's longest chains in a guild
+///
+/// Returns whether the chain beat their previous best, a user's first chain doesn't count
 pub fn update_server_longest_chains(
     conn: &PgConnection,
     guild_id: GuildId,
     member_id: UserId,
     chain_len: i32,
-) {
+) -> bool {
     use self::server_users::dsl::*;
 
     let mut user = get_or_create_server_user(conn, guild_id, member_id);
 
+    let best = user.longest_chains.iter().copied().max().unwrap_or(0);
     let hash = user.longest_chains.iter().fold(0, |a, v| a + v);
-    println!("{:?}", user.longest_chains);
     user.longest_chains.push(chain_len);
-    println!("{:?}", user.longest_chains);
     user.longest_chains.sort();
     user.longest_chains.reverse();
     user.longest_chains.pop();
-    println!("{:?}", user.longest_chains);
 
     if hash != user.longest_chains.iter().fold(0, |a, v| a + v) {
-        let filter = server_users.filter(user_id.eq::<U64Wrapper>(member_id.0.into()));
+        let filter = server_users
+            .filter(server_id.eq::<U64Wrapper>(guild_id.0.into()))
+            .filter(user_id.eq::<U64Wrapper>(member_id.0.into()));
         diesel::update(filter)
             .set(longest_chains.eq(user.longest_chains.clone()))
             .execute(conn)
             .unwrap();
     }
+
+    best > 0 && chain_len > best
 }
@@ -6,6 +6,9 @@
 
    TODO: Change return types to Results
 */
+pub mod audit;
 pub mod guilds;
+pub mod history;
 pub mod leaderboards;
+pub mod privacy;
 pub mod users;
@@ -0,0 +1,53 @@
+use diesel::{pg::PgConnection, prelude::*};
+use serenity::model::id::UserId;
+
+use crate::database::{
+    schema::*,
+    tables::{audit::anonymize_setting_changes, history::delete_user_history},
+    U64Wrapper,
+};
+
+pub fn get_opted_out_users(conn: &PgConnection) -> Vec<UserId> {
+    use self::user_opt_outs::dsl::*;
+
+    user_opt_outs
+        .select(id)
+        .load::<U64Wrapper>(conn)
+        .unwrap()
+        .into_iter()
+        .map(|u| UserId(u.into()))
+        .collect()
+}
+
+pub fn set_opted_out(conn: &PgConnection, user_id: UserId, opted_out: bool) {
+    use self::user_opt_outs::dsl::*;
+
+    if opted_out {
+        diesel::insert_into(user_opt_outs)
+            .values(id.eq::<U64Wrapper>(user_id.0.into()))
+            .on_conflict_do_nothing()
+            .execute(conn)
+            .unwrap();
+    } else {
+        diesel::delete(user_opt_outs.filter(id.eq::<U64Wrapper>(user_id.0.into())))
+            .execute(conn)
+            .unwrap();
+    }
+}
+
+/// Deletes a user's points, chain records and chain history from every server
+/// and the global leaderboard, and removes them from the settings changes they made
+///
+/// Their opt out is kept so we keep ignoring them if they asked us to
+pub fn delete_user_data(conn: &PgConnection, user_id: UserId) {
+    delete_user_history(conn, user_id);
+    anonymize_setting_changes(conn, user_id);
+
+    let filter =
+        server_users::table.filter(server_users::user_id.eq::<U64Wrapper>(user_id.0.into()));
+    diesel::delete(filter).execute(conn).unwrap();
+
+    diesel::delete(users::table.filter(users::id.eq::<U64Wrapper>(user_id.0.into())))
+        .execute(conn)
+        .unwrap();
+}
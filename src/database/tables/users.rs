@@ -14,6 +14,16 @@ pub fn create_user(conn: &PgConnection, id: UserId) -> UserData {
         .expect("Error creating new user")
 }
 
+pub fn get_user(conn: &PgConnection, user_id: UserId) -> Option<UserData> {
+    use self::users::dsl::*;
+
+    users
+        .filter(id.eq::<U64Wrapper>(user_id.0.into()))
+        .first::<UserData>(conn)
+        .optional()
+        .expect("Error getting user data")
+}
+
 pub fn get_or_create_user(conn: &PgConnection, user_id: UserId) -> UserData {
     use self::users::dsl::*;
 
@@ -1,10 +1,18 @@
 #[macro_use]
 extern crate diesel;
+#[macro_use]
+extern crate diesel_migrations;
 
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
-use bot::guild_settings::{GuildSettingsCache, GuildSettingsStore};
+use bot::{
+    guild_settings::{GuildSettingsCache, GuildSettingsStore, DEFAULT_SETTINGS},
+    components::ComponentHandler,
+    lifecycle::{flush_job, retention_job, GuildLifecycleHandler},
+    locale::{LocaleHandler, UserLocales},
+};
 use chain::{ChainCounter, ChainHandler};
+use database::tables::privacy::get_opted_out_users;
 use diesel::PgConnection;
 
 use serenity::{
@@ -33,6 +41,30 @@ impl TypeMapKey for DatabaseConn {
 async fn main() {
     dotenv::dotenv().unwrap();
 
+    // Load the default settings now so mistakes in them stop us from starting
+    lazy_static::initialize(&DEFAULT_SETTINGS);
+
+    // Make sure the database matches the schema we were compiled with before connecting to discord
+    let database_connection = database::establish_connection();
+
+    if std::env::args().any(|a| a == "--check-migrations") {
+        let pending =
+            database::pending_migrations(&database_connection).expect("Error checking migrations");
+
+        if pending.is_empty() {
+            println!("No pending migrations");
+            std::process::exit(0);
+        } else {
+            println!("Pending migrations:");
+            for migration in pending {
+                println!("  {}", migration);
+            }
+            std::process::exit(1);
+        }
+    }
+
+    database::run_migrations(&database_connection).expect("Error running migrations");
+
     // Load in environment vars
     let token = std::env::var("DISCORD_TOKEN").expect("No token provided");
 
@@ -43,14 +75,35 @@ async fn main() {
     let testing_guilds =
         serde_json::from_str::<Vec<GuildId>>(&testing_guilds).expect("Error in TESTING_GUILDS");
 
+    // How long we keep the data of guilds we have been removed from
+    let retention_days = std::env::var("GUILD_RETENTION_DAYS").unwrap_or("30".to_owned());
+    let retention_days =
+        serde_json::from_str::<u64>(&retention_days).expect("Error in GUILD_RETENTION_DAYS");
+
+    // How often changed guild settings are written to the database
+    let flush_seconds = std::env::var("SETTINGS_FLUSH_SECONDS").unwrap_or("30".to_owned());
+    let flush_seconds =
+        serde_json::from_str::<u64>(&flush_seconds).expect("Error in SETTINGS_FLUSH_SECONDS");
+    // A zero interval would make the flush job panic
+    if flush_seconds == 0 {
+        panic!("SETTINGS_FLUSH_SECONDS has to be at least 1");
+    }
+
     let guild_setting_cache = Arc::new(RwLock::new(GuildSettingsCache::new(testing_guilds)));
 
     let framework = Framework::new(guild_setting_cache.clone(), application_id, token.clone())
         .await
+        // Before the other handlers so commands can answer in the user's locale
+        .event_handler(LocaleHandler)
         .event_handler(ChainHandler)
+        .event_handler(GuildLifecycleHandler)
+        .event_handler(ComponentHandler)
         .command::<TOP_COMMAND>()
         .command::<STATS_COMMAND>()
-        .command::<SETTINGS_COMMAND>();
+        .command::<SettingsCommand>()
+        .command::<PRIVACY_COMMAND>()
+        .command::<CHAIN_COMMAND>()
+        .command::<COMPARE_COMMAND>();
 
 
     let mut client = Client::builder(token)
@@ -64,18 +117,52 @@ async fn main() {
     // Add chain store
     data.insert::<ChainCounter>(HashMap::default());
 
+    // Add the users who opted out of chains
+    data.insert::<OptOutStore>(
+        get_opted_out_users(&database_connection)
+            .into_iter()
+            .collect(),
+    );
+
+    // Add the locales of users' clients
+    data.insert::<UserLocales>(Arc::new(RwLock::new(HashMap::new())));
+
     // Add database connection
-    data.insert::<DatabaseConn>(Arc::new(Mutex::new(database::establish_connection())));
+    data.insert::<DatabaseConn>(Arc::new(Mutex::new(database_connection)));
 
     // Load in guild data from the database
     guild_setting_cache.write().await.load_guilds();
     // Add guild settings cache
     data.insert::<GuildSettingsStore>(guild_setting_cache.clone());
 
+    // Purge data of guilds we have left in the background
+    tokio::spawn(retention_job(
+        guild_setting_cache.clone(),
+        Duration::from_secs(retention_days * 24 * 60 * 60),
+    ));
+
+    // Save changed settings in the background
+    tokio::spawn(flush_job(
+        guild_setting_cache.clone(),
+        Duration::from_secs(flush_seconds),
+    ));
+
     // We have to drop data before we start or the RwLock will never let us access it in commands
     drop(data);
 
+    // Shut down cleanly on ctrl-c so unflushed settings aren't lost
+    let shard_manager = client.shard_manager.clone();
+    tokio::spawn(async move {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Error listening for ctrl-c");
+        shard_manager.lock().await.shutdown_all().await;
+    });
+
     if let Err(err) = client.start().await {
         println!("Client encountered an error: {:?}", err);
     }
+
+    let flushed = guild_setting_cache.write().await.flush();
+    println!("Saved settings for {} guilds before shutting down", flushed);
 }